@@ -0,0 +1,159 @@
+use std::time::Duration;
+
+use crate::game::Game;
+use crate::input::Input;
+
+pub const DEFAULT_DAS: Duration = Duration::from_millis(167); // 10 ticks
+pub const DEFAULT_ARR: Duration = Duration::from_millis(33); // 2 ticks
+
+// delayed auto shift: a held left or right key moves the block once, waits das,
+// then keeps moving it every arr. an arr of zero moves it to the wall at once
+#[derive(Debug, Clone)]
+pub struct AutoShift {
+    pub das: Duration,
+    pub arr: Duration,
+    held: Option<Input>, // the direction being held, the last one pressed wins
+    timer: Duration, // how long it has been held
+}
+
+impl Default for AutoShift {
+    fn default() -> Self {
+        AutoShift::new(DEFAULT_DAS, DEFAULT_ARR)
+    }
+}
+
+impl AutoShift {
+    pub fn new(das: Duration, arr: Duration) -> AutoShift {
+        AutoShift {
+            das,
+            arr,
+            held: None,
+            timer: Duration::ZERO,
+        }
+    }
+
+    // the key went down, the move for the press itself is up to the caller
+    pub fn press(&mut self, direction: Input) {
+        self.held = Some(direction);
+        self.timer = Duration::ZERO;
+    }
+
+    // the key is known to have been held for at least das already, repeat right away
+    pub fn hold(&mut self, direction: Input) {
+        if self.held != Some(direction) {
+            self.held = Some(direction);
+            self.timer = self.das;
+        }
+    }
+
+    pub fn release(&mut self, direction: Input) {
+        if self.held == Some(direction) {
+            self.held = None;
+        }
+    }
+
+    pub fn held(&self) -> Option<Input> {
+        self.held
+    }
+
+    // the repeated moves due within the next dt, never more than the block has room for
+    pub fn tick(&mut self, game: &Game, dt: Duration, inputs: &mut Vec<Input>) {
+        let Some(direction) = self.held else {
+            return;
+        };
+        let before = self.repeats(self.timer);
+        self.timer += dt;
+        let due = if self.arr.is_zero() && self.timer >= self.das {
+            // instant, keep the block against the wall even after a new one spawns
+            usize::MAX
+        } else {
+            self.repeats(self.timer) - before
+        };
+        if due == 0 {
+            return;
+        }
+
+        let block = game.current_block();
+        let dx = if direction == Input::Left { -1 } else { 1 };
+        let room = (1..)
+            .take_while(|&n| block.test_position(game.board(), block.state(), block.x() + dx * n, block.y()))
+            .count();
+        inputs.extend(std::iter::repeat_n(direction, due.min(room)));
+    }
+
+    // moves made after holding for the given time, one once das has passed and one more every arr
+    fn repeats(&self, held: Duration) -> usize {
+        if held < self.das {
+            0
+        } else {
+            ((held - self.das).as_nanos() / self.arr.as_nanos().max(1)) as usize + 1
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::clock::TICK;
+    use crate::game::GameOptions;
+
+    fn game() -> Game {
+        Game::with_options(GameOptions { seed: 1, ..GameOptions::default() })
+    }
+
+    // moves made while playing the given number of ticks
+    fn held_for(auto_shift: &mut AutoShift, game: &mut Game, ticks: u32) -> usize {
+        let mut moved = 0;
+        for _ in 0..ticks {
+            let mut inputs = Vec::new();
+            auto_shift.tick(game, TICK, &mut inputs);
+            moved += inputs.len();
+            game.step(&inputs);
+        }
+
+        moved
+    }
+
+    #[test]
+    fn repeats_start_at_das_then_come_every_arr() {
+        let auto_shift = AutoShift::new(Duration::from_millis(100), Duration::from_millis(20));
+        let repeats = |millis| auto_shift.repeats(Duration::from_millis(millis));
+        assert_eq!([0, 99, 100, 119, 120, 160].map(repeats), [0, 0, 1, 1, 2, 4]);
+    }
+
+    #[test]
+    fn held_keys_wait_for_das() {
+        let mut game = game();
+        let mut auto_shift = AutoShift::new(TICK * 10, TICK * 2);
+        auto_shift.press(Input::Right);
+
+        assert_eq!(held_for(&mut auto_shift, &mut game, 9), 0);
+        assert_eq!(held_for(&mut auto_shift, &mut game, 1), 1);
+        assert_eq!(held_for(&mut auto_shift, &mut game, 4), 2);
+
+        auto_shift.release(Input::Right);
+        assert_eq!(held_for(&mut auto_shift, &mut game, 10), 0);
+    }
+
+    #[test]
+    fn zero_arr_moves_to_the_wall_at_once() {
+        let mut game = game();
+        let mut auto_shift = AutoShift::new(TICK * 2, Duration::ZERO);
+        auto_shift.press(Input::Left);
+
+        assert_eq!(held_for(&mut auto_shift, &mut game, 1), 0);
+        assert!(held_for(&mut auto_shift, &mut game, 1) > 0);
+        let block = game.current_block();
+        assert!(!block.test_position(game.board(), block.state(), block.x() - 1, block.y()));
+        assert_eq!(held_for(&mut auto_shift, &mut game, 10), 0);
+    }
+
+    #[test]
+    fn a_key_known_to_be_held_skips_das() {
+        let mut game = game();
+        let mut auto_shift = AutoShift::new(TICK * 10, TICK * 2);
+        auto_shift.hold(Input::Left);
+
+        assert_eq!(held_for(&mut auto_shift, &mut game, 2), 1);
+    }
+}
\ No newline at end of file
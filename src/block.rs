@@ -1,22 +1,82 @@
-use rand::thread_rng;
-use rand::seq::SliceRandom;
-
 use crate::board::Board;
 
-pub const STARTING_X: u8 = 5;
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BlockType {
+    I,
+    J,
+    L,
+    O,
+    S,
+    Z,
+    T,
+}
 
-#[derive(Debug)]
+// SRS wall kick offsets, tried in order until one fits.
+// rows are the rotations 0->R, R->0, R->2, 2->R, 2->L, L->2, L->0, 0->L
+// offsets are (x, y) with y pointing up, as in the guideline tables.
+const JLSTZ_KICKS: [[(i8, i8); 5]; 8] = [
+    [(0, 0), (-1, 0), (-1, 1), (0, -2), (-1, -2)],
+    [(0, 0), (1, 0), (1, -1), (0, 2), (1, 2)],
+    [(0, 0), (1, 0), (1, -1), (0, 2), (1, 2)],
+    [(0, 0), (-1, 0), (-1, 1), (0, -2), (-1, -2)],
+    [(0, 0), (1, 0), (1, 1), (0, -2), (1, -2)],
+    [(0, 0), (-1, 0), (-1, -1), (0, 2), (-1, 2)],
+    [(0, 0), (-1, 0), (-1, -1), (0, 2), (-1, 2)],
+    [(0, 0), (1, 0), (1, 1), (0, -2), (1, -2)],
+];
+
+const I_KICKS: [[(i8, i8); 5]; 8] = [
+    [(0, 0), (-2, 0), (1, 0), (-2, -1), (1, 2)],
+    [(0, 0), (2, 0), (-1, 0), (2, 1), (-1, -2)],
+    [(0, 0), (-1, 0), (2, 0), (-1, 2), (2, -1)],
+    [(0, 0), (1, 0), (-2, 0), (1, -2), (-2, 1)],
+    [(0, 0), (2, 0), (-1, 0), (2, 1), (-1, -2)],
+    [(0, 0), (-2, 0), (1, 0), (-2, -1), (1, 2)],
+    [(0, 0), (1, 0), (-2, 0), (1, -2), (-2, 1)],
+    [(0, 0), (-1, 0), (2, 0), (-1, 2), (2, -1)],
+];
+
+// the O block never kicks
+const O_KICKS: [(i8, i8); 1] = [(0, 0)];
+
+#[derive(Debug, Clone)]
 pub struct Block {
-    pub x: u8,
-    pub y: u8,
-    pub states: [[[u8; 4]; 4]; 4],
-    pub current_state: u8,
-    pub shape: [[u8; 4]; 4],
+    kind: BlockType,
+    x: i16, // may be negative while empty columns of the shape hang past the left wall
+    y: i16, // negative while the block pokes out above the field
+    states: [[[u8; 4]; 4]; 4],
+    current_state: u8,
 }
 
 impl Block {
-    // clockwise
-    pub fn rotate_right(&mut self, board: &Board) {
+    pub fn kind(&self) -> BlockType {
+        self.kind
+    }
+
+    pub fn x(&self) -> i16 {
+        self.x
+    }
+
+    pub fn y(&self) -> i16 {
+        self.y
+    }
+
+    pub fn state(&self) -> u8 {
+        self.current_state
+    }
+
+    // the 4x4 box of the current state, 0 where it is empty
+    pub fn shape(&self) -> &[[u8; 4]; 4] {
+        &self.states[self.current_state as usize]
+    }
+
+    // the shape in its spawn orientation, as the previews show it
+    pub fn spawn_shape(&self) -> &[[u8; 4]; 4] {
+        &self.states[0]
+    }
+
+    // clockwise, returns the index of the wall kick used or None if the block could not rotate
+    pub fn rotate_right(&mut self, board: &Board) -> Option<usize> {
         let mut curr_state = self.current_state;
         if self.current_state + 1 >= self.states.len() as u8 {
             curr_state = 0;
@@ -24,14 +84,11 @@ impl Block {
             curr_state += 1;
         }
 
-        if self.test_position(board, curr_state, self.x, self.y) == true {
-            self.current_state = curr_state;
-            self.shape = self.states[self.current_state as usize];
-        }
+        self.rotate(board, curr_state, self.current_state as usize * 2)
     }
 
-    // counterclockwise
-    pub fn rotate_left(&mut self, board: &Board) {
+    // counterclockwise, returns the index of the wall kick used or None if the block could not rotate
+    pub fn rotate_left(&mut self, board: &Board) -> Option<usize> {
         let mut curr_state = self.current_state;
         if self.current_state == 0 {
             curr_state = (self.states.len() - 1) as u8;
@@ -39,35 +96,76 @@ impl Block {
             curr_state -= 1;
         }
 
-        if self.test_position(board, curr_state, self.x, self.y) == true {
-            self.current_state = curr_state;
-            self.shape = self.states[self.current_state as usize];
+        self.rotate(board, curr_state, (self.current_state as usize * 2 + 7) % 8)
+    }
+
+    fn kicks(&self, rotation: usize) -> &'static [(i8, i8)] {
+        match self.kind {
+            BlockType::I => &I_KICKS[rotation],
+            BlockType::O => &O_KICKS,
+            _ => &JLSTZ_KICKS[rotation],
         }
     }
 
-    pub fn test_position(&self, board: &Board, state: u8, x: u8, y: u8) -> bool {
+    fn rotate(&mut self, board: &Board, state: u8, rotation: usize) -> Option<usize> {
+        for (index, &(kick_x, kick_y)) in self.kicks(rotation).iter().enumerate() {
+            // board rows grow downwards, kick offsets grow upwards
+            let x = self.x + kick_x as i16;
+            let y = self.y - kick_y as i16;
+
+            if self.test_position(board, state, x, y) {
+                self.x = x;
+                self.y = y;
+                self.current_state = state;
+                return Some(index);
+            }
+        }
+
+        None
+    }
+
+    // every occupied cell of the shape has to be inside the walls and on an empty cell
+    pub fn test_position(&self, board: &Board, state: u8, x: i16, y: i16) -> bool {
         for state_x in 0..4 {
             for state_y in 0..4 {
-                if self.states[state as usize][state_y][state_x] != 0 {
-                    let board_x = x as usize + state_x;
-                    let board_y = y as usize + state_y;
-    
-                    if board_x >= 10 || board_y >= 20 {
-                        return false;
-                    }
-    
-                    if board.get_position(board_x as u8, board_y as u8) != 0 {
-                        return false;
-                    }
+                if self.states[state as usize][state_y][state_x] != 0
+                    && board.is_occupied(x + state_x as i16, y + state_y as i16)
+                {
+                    return false;
                 }
-    
             }
         }
 
         true
     }
 
-    pub fn move_position(&mut self, board: &Board, x: u8, y: u8) -> bool {
+    // board coordinates and values of the occupied cells of the current shape
+    pub fn cells(&self) -> impl Iterator<Item = (i16, i16, u8)> + '_ {
+        self.shape().iter().enumerate().flat_map(move |(shape_y, row)| {
+            row.iter()
+                .enumerate()
+                .filter(|(_, &value)| value != 0)
+                .map(move |(shape_x, &value)| (self.x + shape_x as i16, self.y + shape_y as i16, value))
+        })
+    }
+
+    // lowest y the block can fall to from its current position
+    pub fn drop_y(&self, board: &Board) -> i16 {
+        let mut y = self.y;
+        while self.test_position(board, self.current_state, self.x, y + 1) {
+            y += 1;
+        }
+
+        y
+    }
+
+    // put the block back in its spawn position and orientation
+    pub fn reset(&mut self, board: &Board) {
+        (self.x, self.y) = board.spawn_position();
+        self.current_state = 0;
+    }
+
+    pub fn move_position(&mut self, board: &Board, x: i16, y: i16) -> bool {
         if self.test_position(board, self.current_state, x, y) {
             self.x = x;
             self.y = y;
@@ -79,6 +177,9 @@ impl Block {
     }
 }
 
+// the SRS rotation states, clockwise from spawn. J, L, S, T and Z turn around the center of
+// their 3x3 box, I and O around the center of the 4x4 box, so the O never moves
+#[allow(clippy::new_ret_no_self)]
 trait BlockCreator {
     fn new() -> Block;
 }
@@ -88,7 +189,8 @@ pub struct I; // I block
 impl BlockCreator for I {
     fn new() -> Block {
         Block {
-            x: STARTING_X,
+            kind: BlockType::I,
+            x: 0,
             y: 0,
             states: [
                 [
@@ -117,12 +219,6 @@ impl BlockCreator for I {
                 ],
             ],
             current_state: 0,
-            shape: [
-                [0, 0, 0, 0],
-                [1, 1, 1, 1],
-                [0, 0, 0, 0],
-                [0, 0, 0, 0],
-            ]
         }
     }
 }
@@ -132,41 +228,36 @@ pub struct J; // J block
 impl BlockCreator for J {
     fn new() -> Block {
         Block {
-            x: STARTING_X,
+            kind: BlockType::J,
+            x: 0,
             y: 0,
             states: [
                 [
+                    [2, 0, 0, 0],
                     [2, 2, 2, 0],
-                    [0, 0, 2, 0],
                     [0, 0, 0, 0],
                     [0, 0, 0, 0],
                 ],
                 [
+                    [0, 2, 2, 0],
                     [0, 2, 0, 0],
                     [0, 2, 0, 0],
-                    [2, 2, 0, 0],
                     [0, 0, 0, 0],
                 ],
                 [
                     [0, 0, 0, 0],
-                    [2, 0, 0, 0],
                     [2, 2, 2, 0],
+                    [0, 0, 2, 0],
                     [0, 0, 0, 0],
                 ],
                 [
-                    [0, 2, 2, 0],
                     [0, 2, 0, 0],
                     [0, 2, 0, 0],
+                    [2, 2, 0, 0],
                     [0, 0, 0, 0],
                 ],
             ],
             current_state: 0,
-            shape: [
-                [2, 2, 2, 0],
-                [0, 0, 2, 0],
-                [0, 0, 0, 0],
-                [0, 0, 0, 0],
-            ],
         }
     }
 }
@@ -176,41 +267,36 @@ pub struct L; // L block
 impl BlockCreator for L {
     fn new() -> Block {
         Block {
-            x: STARTING_X,
+            kind: BlockType::L,
+            x: 0,
             y: 0,
             states: [
                 [
-                    [0, 0, 0, 0],
+                    [0, 0, 3, 0],
                     [3, 3, 3, 0],
-                    [3, 0, 0, 0],
+                    [0, 0, 0, 0],
                     [0, 0, 0, 0],
                 ],
                 [
-                    [3, 3, 0, 0],
                     [0, 3, 0, 0],
                     [0, 3, 0, 0],
+                    [0, 3, 3, 0],
                     [0, 0, 0, 0],
                 ],
                 [
                     [0, 0, 0, 0],
-                    [0, 0, 3, 0],
                     [3, 3, 3, 0],
+                    [3, 0, 0, 0],
                     [0, 0, 0, 0],
                 ],
                 [
+                    [3, 3, 0, 0],
                     [0, 3, 0, 0],
                     [0, 3, 0, 0],
-                    [0, 3, 3, 0],
                     [0, 0, 0, 0],
                 ],
             ],
             current_state: 0,
-            shape: [
-                [0, 0, 0, 0],
-                [3, 3, 3, 0],
-                [3, 0, 0, 0],
-                [0, 0, 0, 0],
-            ],
         }
     }
 }
@@ -221,41 +307,36 @@ pub struct O; // O block
 impl BlockCreator for O {
     fn new() -> Block {
         Block {
-            x: STARTING_X,
+            kind: BlockType::O,
+            x: 0,
             y: 0,
             states: [
                 [
-                    [4, 4, 0, 0],
-                    [4, 4, 0, 0],
+                    [0, 4, 4, 0],
+                    [0, 4, 4, 0],
                     [0, 0, 0, 0],
                     [0, 0, 0, 0],
                 ],
                 [
-                    [4, 4, 0, 0],
-                    [4, 4, 0, 0],
+                    [0, 4, 4, 0],
+                    [0, 4, 4, 0],
                     [0, 0, 0, 0],
                     [0, 0, 0, 0],
                 ],
                 [
-                    [4, 4, 0, 0],
-                    [4, 4, 0, 0],
+                    [0, 4, 4, 0],
+                    [0, 4, 4, 0],
                     [0, 0, 0, 0],
                     [0, 0, 0, 0],
                 ],
                 [
-                    [4, 4, 0, 0],
-                    [4, 4, 0, 0],
+                    [0, 4, 4, 0],
+                    [0, 4, 4, 0],
                     [0, 0, 0, 0],
                     [0, 0, 0, 0],
                 ],
             ],
             current_state: 0,
-            shape: [
-                [4, 4, 0, 0],
-                [4, 4, 0, 0],
-                [0, 0, 0, 0],
-                [0, 0, 0, 0],
-            ],
         }
     }
 }
@@ -266,19 +347,20 @@ pub struct S; // S block
 impl BlockCreator for S {
     fn new() -> Block {
         Block {
-            x: STARTING_X,
+            kind: BlockType::S,
+            x: 0,
             y: 0,
             states: [
                 [
-                    [0, 0, 0, 0],
                     [0, 5, 5, 0],
                     [5, 5, 0, 0],
                     [0, 0, 0, 0],
+                    [0, 0, 0, 0],
                 ],
                 [
-                    [5, 0, 0, 0],
-                    [5, 5, 0, 0],
                     [0, 5, 0, 0],
+                    [0, 5, 5, 0],
+                    [0, 0, 5, 0],
                     [0, 0, 0, 0],
                 ],
                 [
@@ -295,12 +377,6 @@ impl BlockCreator for S {
                 ],
             ],
             current_state: 0,
-            shape: [
-                [0, 0, 0, 0],
-                [0, 5, 5, 0],
-                [5, 5, 0, 0],
-                [0, 0, 0, 0],
-            ],
         }
     }
 }
@@ -311,14 +387,15 @@ pub struct Z; // Z block
 impl BlockCreator for Z {
     fn new() -> Block {
         Block {
-            x: STARTING_X,
+            kind: BlockType::Z,
+            x: 0,
             y: 0,
             states: [
                 [
-                    [0, 0, 0, 0],
                     [6, 6, 0, 0],
                     [0, 6, 6, 0],
                     [0, 0, 0, 0],
+                    [0, 0, 0, 0],
                 ],
                 [
                     [0, 0, 6, 0],
@@ -340,12 +417,6 @@ impl BlockCreator for Z {
                 ],
             ],
             current_state: 0,
-            shape: [
-                [6, 6, 0, 0],
-                [0, 6, 6, 0],
-                [0, 0, 0, 0],
-                [0, 0, 0, 0],
-            ],
         }
     }
 }
@@ -355,7 +426,8 @@ pub struct T; // T block
 impl BlockCreator for T {
     fn new() -> Block {
         Block {
-            x: STARTING_X,
+            kind: BlockType::T,
+            x: 0,
             y: 0,
             states: [
                 [
@@ -384,31 +456,105 @@ impl BlockCreator for T {
                 ],
             ],
             current_state: 0,
-            shape: [
-                [0, 7, 0, 0],
-                [7, 7, 7, 0],
-                [0, 0, 0, 0],
-                [0, 0, 0, 0],
-            ],
         }
     }
 }
 
-// generate 7 block vectors to ensure uniform block distributions.
-pub fn new_chunk() -> [Block; 7]{
-    let mut rng = thread_rng();
-    // initialize chunk of all 7 block types
-    let mut chunk: [Block; 7] = [
-        I::new(),
-        J::new(),
-        L::new(),
-        O::new(),
-        S::new(),
-        Z::new(),
-        T::new(),
-    ];
-
-    chunk.shuffle(&mut rng);
-
-    chunk
+pub fn new_block(kind: BlockType) -> Block {
+    match kind {
+        BlockType::I => I::new(),
+        BlockType::J => J::new(),
+        BlockType::L => L::new(),
+        BlockType::O => O::new(),
+        BlockType::S => S::new(),
+        BlockType::Z => Z::new(),
+        BlockType::T => T::new(),
+    }
+}
+
+// a block turned to the given state and put down at x, y, for setting up tests
+#[cfg(test)]
+pub fn placed_block(kind: BlockType, state: u8, x: i16, y: i16) -> Block {
+    let mut block = new_block(kind);
+    block.current_state = state;
+    (block.x, block.y) = (x, y);
+
+    block
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn rotates_in_place_when_there_is_room() {
+        let board = Board::new();
+        let mut t = placed_block(BlockType::T, 0, 3, 30);
+
+        assert_eq!(t.rotate_right(&board), Some(0));
+        assert_eq!((t.x, t.y, t.current_state), (3, 30, 1));
+    }
+
+    #[test]
+    fn i_kicks_off_the_left_wall() {
+        let board = Board::new();
+        // vertical in the leftmost column, flat does not fit without moving right
+        let mut i = placed_block(BlockType::I, 3, -1, 30);
+
+        assert_eq!(i.rotate_right(&board), Some(1));
+        assert_eq!((i.x, i.y, i.current_state), (0, 30, 0));
+    }
+
+    #[test]
+    fn takes_the_first_kick_that_fits() {
+        let mut board = Board::new();
+        // blocks the R->0 kick (1, 0), so (1, -1) is next: one right and one down
+        board.set_position(2, 31, 1);
+        let mut j = placed_block(BlockType::J, 1, -1, 30);
+
+        assert_eq!(j.rotate_left(&board), Some(2));
+        assert_eq!((j.x, j.y, j.current_state), (0, 31, 0));
+    }
+
+    #[test]
+    fn stays_put_when_no_kick_fits() {
+        let mut board = Board::new();
+        for y in 36..40 {
+            for x in 0..10 {
+                if x != 4 {
+                    board.set_position(x, y, 1);
+                }
+            }
+        }
+        let mut i = placed_block(BlockType::I, 1, 2, 36);
+
+        assert_eq!(i.rotate_right(&board), None);
+        assert_eq!((i.x, i.y, i.current_state), (2, 36, 1));
+    }
+
+    #[test]
+    fn rotation_states_are_srs() {
+        let board = Board::new();
+        let spawn = |kind| {
+            let mut block = new_block(kind);
+            block.reset(&board);
+            let mut cells: Vec<_> = block.cells().map(|(x, y, _)| (x, y)).collect();
+            cells.sort();
+            cells
+        };
+
+        // flat side down in the first visible row, left of center on odd widths
+        assert_eq!(spawn(BlockType::J), vec![(3, 19), (3, 20), (4, 20), (5, 20)]);
+        assert_eq!(spawn(BlockType::L), vec![(3, 20), (4, 20), (5, 19), (5, 20)]);
+        assert_eq!(spawn(BlockType::O), vec![(4, 19), (4, 20), (5, 19), (5, 20)]);
+        assert_eq!(spawn(BlockType::I), vec![(3, 20), (4, 20), (5, 20), (6, 20)]);
+
+        // S and Z have four distinct states, each turning around the same center
+        for kind in [BlockType::S, BlockType::Z] {
+            let block = new_block(kind);
+            assert_ne!(block.states[0], block.states[2]);
+            assert_ne!(block.states[1], block.states[3]);
+            assert_eq!(block.states[0][1][1], block.states[2][1][1]);
+        }
+    }
 }
\ No newline at end of file
@@ -1,25 +1,144 @@
-#[derive(Debug)]
+pub const DEFAULT_WIDTH: u8 = 10;
+pub const DEFAULT_HEIGHT: u8 = 20;
+pub const MIN_SIZE: u8 = 4; // every block has to fit in its spawn orientation
+// larger boards stop fitting on a terminal. there is no big mode, blocks are always one cell per square
+pub const MAX_WIDTH: u8 = 20;
+pub const MAX_HEIGHT: u8 = 40;
+
+#[derive(Debug, PartialEq, Eq)]
 pub struct Board {
-    pub width: u8,
-    pub height: u8,
-    pub board_map: [[u8; 10]; 20],
+    width: u8,
+    height: u8, // visible rows
+    buffer: u8, // hidden rows above the visible field where blocks spawn
+    board_map: Vec<Vec<u8>>, // buffer rows first, then the visible rows
 }
 
+impl Default for Board {
+    fn default() -> Self {
+        Self::new()
+    }
+}
 
 impl Board {
     pub fn new() -> Board {
+        Board::with_size(DEFAULT_WIDTH, DEFAULT_HEIGHT)
+    }
+
+    // the hidden buffer is as tall as the visible field, so 10x20 gives the guideline 10x40 matrix
+    pub fn with_size(width: u8, height: u8) -> Board {
+        let width = width.clamp(MIN_SIZE, MAX_WIDTH);
+        let height = height.clamp(MIN_SIZE, MAX_HEIGHT);
+
         Board {
-            width: 10,
-            height: 20,
-            board_map: [[0u8; 10]; 20]
+            width,
+            height,
+            buffer: height,
+            board_map: vec![vec![0u8; width as usize]; height as usize * 2],
+        }
+    }
+
+    pub fn width(&self) -> u8 {
+        self.width
+    }
+
+    // visible rows
+    pub fn height(&self) -> u8 {
+        self.height
+    }
+
+    // hidden rows above the visible field
+    pub fn buffer(&self) -> u8 {
+        self.buffer
+    }
+
+    // total rows, hidden and visible
+    pub fn rows(&self) -> u8 {
+        self.height + self.buffer
+    }
+
+    // rows in the buffer, and anything above it, are not shown
+    pub fn is_hidden_row(&self, y: i16) -> bool {
+        y < self.buffer as i16
+    }
+
+    // top left corner of the 4x4 box of a newly spawned block, its upper
+    // rows start in the buffer right above the visible field
+    pub fn spawn_position(&self) -> (i16, i16) {
+        ((self.width as i16 - 4) / 2, self.buffer as i16 - 1)
+    }
+
+    // walls and floor count as occupied, the open space above the top row does not
+    pub fn is_occupied(&self, x: i16, y: i16) -> bool {
+        if x < 0 || x >= self.width as i16 || y >= self.rows() as i16 {
+            return true;
         }
+
+        y >= 0 && self.board_map[y as usize][x as usize] != 0
     }
 
     pub fn get_position(&self, x: u8, y: u8) -> u8 {
         self.board_map[y as usize][x as usize]
     }
 
+    // the cells of a row, 0 where it is empty
+    pub fn row(&self, y: usize) -> &[u8] {
+        &self.board_map[y]
+    }
+
     pub fn set_position(&mut self, x: usize, y: usize, value: u8) {
         self.board_map[y][x] = value;
     }
+
+    pub fn is_empty(&self) -> bool {
+        self.board_map.iter().flatten().all(|&cell| cell == 0)
+    }
+
+    pub fn is_row_complete(&self, y: usize) -> bool {
+        self.board_map[y].iter().all(|&cell| cell != 0)
+    }
+
+    // removes every full row and collapses the rows above it down.
+    // returns the indices of the cleared rows (top to bottom) as they were before the collapse.
+    pub fn clear_completed_rows(&mut self) -> Vec<usize> {
+        let cleared: Vec<usize> = (0..self.board_map.len())
+            .filter(|&y| self.is_row_complete(y))
+            .collect();
+
+        if cleared.is_empty() {
+            return cleared;
+        }
+
+        self.board_map.retain(|row| row.contains(&0));
+        for _ in 0..cleared.len() {
+            self.board_map.insert(0, vec![0u8; self.width as usize]);
+        }
+
+        cleared
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn clears_rows_that_are_not_next_to_each_other() {
+        let mut board = Board::with_size(4, 4);
+        board.board_map[4] = vec![0, 2, 0, 0];
+        board.board_map[5] = vec![1, 1, 1, 1];
+        board.board_map[6] = vec![3, 0, 0, 0];
+        board.board_map[7] = vec![1, 1, 1, 1];
+
+        assert_eq!(board.clear_completed_rows(), vec![5, 7]);
+        assert_eq!(board.board_map[7], vec![3, 0, 0, 0]);
+        assert_eq!(board.board_map[6], vec![0, 2, 0, 0]);
+        assert!(board.board_map[..6].iter().flatten().all(|&cell| cell == 0));
+    }
+
+    #[test]
+    fn sizes_are_kept_within_limits() {
+        let board = Board::with_size(255, 1);
+        assert_eq!((board.width, board.height), (MAX_WIDTH, MIN_SIZE));
+        assert_eq!(board.rows() as usize, board.board_map.len());
+    }
 }
\ No newline at end of file
@@ -0,0 +1,53 @@
+use std::time::{Duration, Instant};
+
+pub const TICK_RATE: u32 = 60; // logic ticks per second
+pub const TICK: Duration = Duration::from_nanos(1_000_000_000 / TICK_RATE as u64);
+
+// after a long stall only this many ticks are caught up instead of fast forwarding the game
+const MAX_CATCH_UP_TICKS: u32 = 10;
+
+// fixed timestep clock, real elapsed time is accumulated and handed out in whole ticks
+#[derive(Debug)]
+pub struct GameClock {
+    last: Instant,
+    accumulator: Duration,
+}
+
+impl Default for GameClock {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl GameClock {
+    pub fn new() -> GameClock {
+        GameClock {
+            last: Instant::now(),
+            accumulator: Duration::ZERO,
+        }
+    }
+
+    // number of logic ticks due since the last call
+    pub fn ticks(&mut self) -> u32 {
+        let now = Instant::now();
+        self.accumulator += now - self.last;
+        self.last = now;
+
+        let mut ticks = 0;
+        while self.accumulator >= TICK {
+            self.accumulator -= TICK;
+            ticks += 1;
+        }
+
+        if ticks > MAX_CATCH_UP_TICKS {
+            ticks = MAX_CATCH_UP_TICKS;
+        }
+
+        ticks
+    }
+
+    // how long input polling may block without delaying the next tick
+    pub fn until_next_tick(&self) -> Duration {
+        TICK.saturating_sub(self.accumulator + self.last.elapsed())
+    }
+}
\ No newline at end of file
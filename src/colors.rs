@@ -11,4 +11,18 @@ pub fn get_color(index: u8) -> Color {
         7 => Color::Green,
         _ => Color::White, 
     }
+}
+
+// dimmed palette used for the ghost block
+pub fn get_ghost_color(index: u8) -> Color {
+    match index {
+        1 => Color::DarkRed,
+        2 => Color::DarkBlue,
+        3 => Color::Rgb { r: 95, g: 50, b: 0 },
+        4 => Color::DarkYellow,
+        5 => Color::DarkMagenta,
+        6 => Color::DarkCyan,
+        7 => Color::DarkGreen,
+        _ => Color::Grey,
+    }
 }
\ No newline at end of file
@@ -0,0 +1,199 @@
+use std::io::Result;
+use std::path::PathBuf;
+use std::time::Duration;
+
+use retris::autoshift::{DEFAULT_ARR, DEFAULT_DAS};
+
+use crate::keymap::{Action, Keymap, Preset};
+use crate::storage::{self, invalid};
+
+// settings read from config.toml in the user's config directory, e.g.
+//
+//   preset = "arrows"
+//
+//   [keys]
+//   hard_drop = ["space", "enter"]
+//   hold = "shift+c"
+//
+//   [handling]
+//   das = 133
+//   arr = 0
+//
+// every action listed under [keys] replaces the preset's keys for that action
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Config {
+    pub preset: Preset,
+    pub keys: Vec<(Action, Vec<String>)>, // rebound actions on top of the preset
+    pub das: Duration, // delay before a held left or right key starts repeating
+    pub arr: Duration, // time between repeated moves, zero moves to the wall at once
+}
+
+impl Default for Config {
+    fn default() -> Self {
+        Config {
+            preset: Preset::Vim,
+            keys: Vec::new(),
+            das: DEFAULT_DAS,
+            arr: DEFAULT_ARR,
+        }
+    }
+}
+
+impl Config {
+    // ~/.config/retris/config.toml on linux, the platform equivalent elsewhere
+    pub fn path() -> Option<PathBuf> {
+        dirs::config_dir().map(|dir| dir.join("retris").join("config.toml"))
+    }
+
+    // a missing config file is not an error, the defaults are used instead
+    pub fn load() -> Result<Config> {
+        match storage::load(Config::path())? {
+            Some(table) => Config::from_table(&table),
+            None => Ok(Config::default()),
+        }
+    }
+
+    // written back from the settings menu, comments in the file are not kept
+    pub fn save(&self) -> Result<()> {
+        storage::save(Config::path(), &self.to_table())
+    }
+
+    fn from_table(table: &toml::Table) -> Result<Config> {
+        let mut config = Config::default();
+
+        if let Some(preset) = table.get("preset") {
+            config.preset = preset
+                .as_str()
+                .and_then(Preset::from_name)
+                .ok_or_else(|| invalid(format!("unknown preset {}", preset)))?;
+        }
+
+        if let Some(keys) = table.get("keys") {
+            let keys = keys.as_table().ok_or_else(|| invalid("keys must be a table".to_string()))?;
+            for (name, value) in keys {
+                let action = Action::from_name(name).ok_or_else(|| invalid(format!("unknown action {}", name)))?;
+                // a single key may be given without the list around it
+                let keys = match value {
+                    toml::Value::String(key) => Some(vec![key.clone()]),
+                    toml::Value::Array(keys) => keys.iter().map(|key| key.as_str().map(String::from)).collect(),
+                    _ => None,
+                }
+                .ok_or_else(|| invalid(format!("keys for {} must be a string or a list of strings", name)))?;
+                config.keys.push((action, keys));
+            }
+        }
+
+        if let Some(handling) = table.get("handling") {
+            let handling = handling.as_table().ok_or_else(|| invalid("handling must be a table".to_string()))?;
+            for (name, value) in handling {
+                // both are given in milliseconds
+                let millis = storage::unsigned(value).ok_or_else(|| invalid(format!("{} must be a whole number of milliseconds", name)))?;
+                match name.as_str() {
+                    "das" => config.das = Duration::from_millis(millis),
+                    "arr" => config.arr = Duration::from_millis(millis),
+                    _ => return Err(invalid(format!("unknown handling setting {}", name))),
+                }
+            }
+        }
+
+        if let Some(name) = table.keys().find(|name| !["preset", "keys", "handling"].contains(&name.as_str())) {
+            return Err(invalid(format!("unknown setting {}", name)));
+        }
+
+        // catch unknown key names now rather than when the keymap is built
+        config.try_keymap()?;
+
+        Ok(config)
+    }
+
+    fn to_table(&self) -> toml::Table {
+        let mut table = toml::Table::new();
+        table.insert("preset".to_string(), self.preset.name().into());
+
+        if !self.keys.is_empty() {
+            let keys = self
+                .keys
+                .iter()
+                .map(|(action, keys)| (action.name().to_string(), keys.clone().into()))
+                .collect::<toml::Table>();
+            table.insert("keys".to_string(), keys.into());
+        }
+
+        let mut handling = toml::Table::new();
+        handling.insert("das".to_string(), (self.das.as_millis() as i64).into());
+        handling.insert("arr".to_string(), (self.arr.as_millis() as i64).into());
+        table.insert("handling".to_string(), handling.into());
+
+        table
+    }
+
+    // the preset with the rebound actions applied
+    pub fn keymap(&self) -> Keymap {
+        self.try_keymap().expect("keys are checked when the config is parsed")
+    }
+
+    fn try_keymap(&self) -> Result<Keymap> {
+        let mut keymap = Keymap::preset(self.preset);
+        for (action, keys) in &self.keys {
+            let keys = keys.iter().map(String::as_str).collect::<Vec<_>>();
+            keymap.bind(*action, &keys)?;
+        }
+
+        Ok(keymap)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crossterm::event::{KeyCode, KeyEvent, KeyModifiers};
+
+    fn parse(text: &str) -> Result<Config> {
+        Config::from_table(&text.parse().unwrap())
+    }
+
+    #[test]
+    fn keys_are_a_string_or_a_list() {
+        let config = parse("[keys]\nhold = \"shift+c\"\nhard_drop = [\"space\", \"enter\"]").unwrap();
+        let keymap = config.keymap();
+
+        let key = |code| KeyEvent::new(code, KeyModifiers::NONE);
+        assert_eq!(keymap.action(&KeyEvent::new(KeyCode::Char('C'), KeyModifiers::SHIFT)), Some(Action::Hold));
+        assert_eq!(keymap.action(&key(KeyCode::Enter)), Some(Action::HardDrop));
+        assert_eq!(keymap.action(&key(KeyCode::Char(' '))), Some(Action::HardDrop));
+        // rebinding replaces the preset's keys for the action
+        assert_eq!(keymap.action(&key(KeyCode::Char('c'))), None);
+
+        assert!(parse("[keys]\nhold = 5").is_err());
+        assert!(parse("[keys]\nhold = [\"c\", 5]").is_err());
+    }
+
+    #[test]
+    fn unknown_names_are_rejected() {
+        assert!(parse("preset = \"emacs\"").is_err());
+        assert!(parse("colors = true").is_err());
+        assert!(parse("[keys]\njump = \"space\"").is_err());
+        assert!(parse("[keys]\nhold = \"hyper+c\"").is_err());
+        assert!(parse("[handling]\nspeed = 10").is_err());
+    }
+
+    #[test]
+    fn handling_is_in_milliseconds() {
+        let config = parse("[handling]\ndas = 133\narr = 0").unwrap();
+        assert_eq!(config.das, Duration::from_millis(133));
+        assert_eq!(config.arr, Duration::ZERO);
+
+        assert!(parse("[handling]\ndas = -1").is_err());
+        assert!(parse("[handling]\narr = 1.5").is_err());
+    }
+
+    #[test]
+    fn saved_settings_load_the_same() {
+        let config = parse("preset = \"wasd\"\n[keys]\nhold = [\"c\", \"shift+c\"]\n[handling]\ndas = 100").unwrap();
+        let again = Config::from_table(&config.to_table()).unwrap();
+
+        assert_eq!(again.preset, Preset::Wasd);
+        assert_eq!(again.keys, config.keys);
+        assert_eq!((again.das, again.arr), (Duration::from_millis(100), DEFAULT_ARR));
+    }
+}
\ No newline at end of file
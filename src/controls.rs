@@ -0,0 +1,205 @@
+use std::time::{Duration, Instant};
+
+use crossterm::event::{KeyEvent, KeyEventKind};
+use retris::{AutoShift, Game, Input, SoftDrop};
+
+use crate::keymap::{Action, Keymap};
+
+// without release events a held key only shows up as the terminal's key repeat.
+// presses of the same key closer together than this are repeats, a longer gap means it was let go
+const REPEAT_GAP: Duration = Duration::from_millis(100);
+// quick presses in a row before a key counts as held. a double tap is two of them,
+// only the key repeat keeps them coming
+const PRESSES_TO_HOLD: u32 = 3;
+
+// turns key events into game inputs, with auto shift for held left and right keys
+// and faster gravity for a held soft drop key
+#[derive(Debug)]
+pub struct Controls {
+    keymap: Keymap,
+    auto_shift: AutoShift,
+    release_events: bool, // the terminal reports key releases, otherwise they are guessed from the key repeat
+    shift_presses: Presses, // left and right presses, to tell repeats from taps
+    soft_drop: SoftDrop,
+    soft_drop_presses: Presses,
+}
+
+impl Controls {
+    pub fn new(keymap: Keymap, auto_shift: AutoShift, release_events: bool) -> Controls {
+        Controls {
+            keymap,
+            auto_shift,
+            release_events,
+            shift_presses: Presses::default(),
+            soft_drop: SoftDrop::new(),
+            soft_drop_presses: Presses::default(),
+        }
+    }
+
+    // new bindings and timings from the settings menu
+    pub fn configure(&mut self, keymap: Keymap, auto_shift: AutoShift) {
+        self.keymap = keymap;
+        self.auto_shift = auto_shift;
+        self.shift_presses = Presses::default();
+        self.soft_drop = SoftDrop::new();
+        self.soft_drop_presses = Presses::default();
+    }
+
+    // forget held keys, e.g. when a new game starts
+    pub fn reset(&mut self) {
+        self.auto_shift = AutoShift::new(self.auto_shift.das, self.auto_shift.arr);
+        self.shift_presses = Presses::default();
+        self.soft_drop = SoftDrop::new();
+        self.soft_drop_presses = Presses::default();
+    }
+
+    // game inputs for the key are pushed to inputs, any other action bound to it is returned
+    pub fn key(&mut self, event: &KeyEvent, inputs: &mut Vec<Input>) -> Option<Action> {
+        // some terminals report releases without being asked to
+        self.release_events |= event.kind == KeyEventKind::Release;
+        let action = self.keymap.action(event)?;
+
+        let Some(input) = action.input() else {
+            return (event.kind == KeyEventKind::Press).then_some(action);
+        };
+
+        match (input, event.kind) {
+            (Input::Left | Input::Right, KeyEventKind::Release) => self.auto_shift.release(input),
+            (Input::Left | Input::Right, KeyEventKind::Press) if !self.release_events => self.guess_shift(input, inputs),
+            (Input::Left | Input::Right, KeyEventKind::Press) => {
+                inputs.push(input);
+                self.auto_shift.press(input);
+            }
+            (Input::SoftDrop, KeyEventKind::Release) => self.soft_drop.release(),
+            (Input::SoftDrop, KeyEventKind::Press) if !self.release_events => self.guess_soft_drop(inputs),
+            (Input::SoftDrop, KeyEventKind::Press) => {
+                inputs.push(input);
+                self.soft_drop.press();
+            }
+            (_, KeyEventKind::Press) => inputs.push(input),
+            _ => (),
+        }
+
+        None
+    }
+
+    // the menu action for the key while a menu is open, quit works there as well
+    pub fn menu_key(&self, event: &KeyEvent) -> Option<Action> {
+        if event.kind == KeyEventKind::Release {
+            return None;
+        }
+
+        self.keymap
+            .menu_action(event)
+            .or_else(|| self.keymap.action(event).filter(|&action| action == Action::Quit))
+    }
+
+    // taps move once each. once the presses of a direction come as steadily as the terminal's
+    // key repeat, the held key shifts at the configured rate instead of the terminal's
+    fn guess_shift(&mut self, input: Input, inputs: &mut Vec<Input>) {
+        if self.shift_presses.press(input, Instant::now()) {
+            self.auto_shift.hold(input);
+        } else {
+            inputs.push(input);
+            if let Some(held) = self.auto_shift.held() {
+                self.auto_shift.release(held);
+            }
+        }
+    }
+
+    // like guess_shift, a held soft drop key drops at the soft drop rate instead of the terminal's
+    fn guess_soft_drop(&mut self, inputs: &mut Vec<Input>) {
+        if self.soft_drop_presses.press(Input::SoftDrop, Instant::now()) {
+            if !self.soft_drop.is_held() {
+                self.soft_drop.press();
+            }
+        } else {
+            inputs.push(Input::SoftDrop);
+        }
+    }
+
+    // moves from held keys for the next logic tick
+    pub fn tick(&mut self, game: &Game, dt: Duration, inputs: &mut Vec<Input>) {
+        if !self.release_events {
+            let now = Instant::now();
+            if let Some(held) = self.auto_shift.held().filter(|_| self.shift_presses.stopped(now)) {
+                self.auto_shift.release(held);
+            }
+            if self.soft_drop_presses.stopped(now) {
+                self.soft_drop.release();
+            }
+        }
+
+        self.auto_shift.tick(game, dt, inputs);
+        self.soft_drop.tick(game, dt, inputs);
+    }
+}
+
+// recent presses of one key, to tell the terminal's key repeat from quick taps
+#[derive(Debug, Default)]
+struct Presses {
+    key: Option<Input>,
+    last: Option<Instant>,
+    in_a_row: u32, // presses of the key, each within REPEAT_GAP of the one before
+}
+
+impl Presses {
+    // true once the key keeps coming often enough to be held down
+    fn press(&mut self, key: Input, now: Instant) -> bool {
+        let repeat = self.key == Some(key) && self.last.is_some_and(|at| now - at <= REPEAT_GAP);
+        self.in_a_row = if repeat { self.in_a_row + 1 } else { 1 };
+        self.key = Some(key);
+        self.last = Some(now);
+
+        self.in_a_row >= PRESSES_TO_HOLD
+    }
+
+    // no press came for longer than the key repeat would take, the key was let go
+    fn stopped(&self, now: Instant) -> bool {
+        self.last.is_some_and(|at| now - at > REPEAT_GAP)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const TAP_GAP: Duration = Duration::from_millis(80);
+    const KEY_REPEAT: Duration = Duration::from_millis(30);
+
+    #[test]
+    fn a_double_tap_is_not_held() {
+        let mut presses = Presses::default();
+        let start = Instant::now();
+        assert!(!presses.press(Input::Left, start));
+        assert!(!presses.press(Input::Left, start + TAP_GAP));
+        assert!(presses.stopped(start + TAP_GAP + REPEAT_GAP * 2));
+    }
+
+    #[test]
+    fn steady_key_repeat_is_held() {
+        let mut presses = Presses::default();
+        let start = Instant::now();
+        // the first repeat only comes after the terminal's own delay
+        assert!(!presses.press(Input::Right, start));
+        let mut at = start + Duration::from_millis(300);
+        assert!(!presses.press(Input::Right, at));
+        for _ in 1..PRESSES_TO_HOLD - 1 {
+            at += KEY_REPEAT;
+            assert!(!presses.press(Input::Right, at));
+        }
+        at += KEY_REPEAT;
+        assert!(presses.press(Input::Right, at));
+        assert!(!presses.stopped(at + KEY_REPEAT));
+    }
+
+    #[test]
+    fn another_key_starts_over() {
+        let mut presses = Presses::default();
+        let start = Instant::now();
+        for i in 0..PRESSES_TO_HOLD {
+            presses.press(Input::Left, start + KEY_REPEAT * i);
+        }
+        assert!(!presses.press(Input::Right, start + KEY_REPEAT * PRESSES_TO_HOLD));
+    }
+}
\ No newline at end of file
@@ -1,165 +1,722 @@
-use crate::block::{new_chunk, Block, STARTING_X};
-use crate::board::Board;
-use crate::colors::get_color;
+use crate::block::{Block, BlockType};
+use crate::board::{Board, DEFAULT_HEIGHT, DEFAULT_WIDTH, MAX_HEIGHT, MAX_WIDTH, MIN_SIZE};
+use crate::clock::TICK;
 use crate::input::Input;
+use crate::level::{gravity, level_for, MAX_START_LEVEL};
+use crate::queue::BlockQueue;
+use crate::randomizer::RandomizerKind;
+use crate::replay::Replay;
+use crate::scoring::{Scoring, TSpin};
 
-use crossterm::cursor::MoveTo;
-use crossterm::execute;
-use crossterm::style::{Print, SetForegroundColor, ResetColor};
-use std::io::Stdout;
+use std::time::Duration;
 
-const MARGIN: u8 = 2; // side margin for cursor calculations
+pub const LOCK_DELAY: Duration = Duration::from_millis(500);
+const MAX_LOCK_RESETS: u8 = 15; // moves that may restart the lock delay before the block locks on touching down
+pub const SPLIT_LINES: u32 = 10; // a split time is taken every this many lines
 
+// what the game is played for
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum GameMode {
+    Endless, // no goal, play for score until the stack tops out
+    Sprint(u32), // clear this many lines as fast as possible
+}
+
+impl GameMode {
+    pub const ALL: [GameMode; 4] = [
+        GameMode::Endless,
+        GameMode::Sprint(20),
+        GameMode::Sprint(40),
+        GameMode::Sprint(100),
+    ];
+
+    pub fn name(&self) -> String {
+        match self {
+            GameMode::Endless => "endless".to_string(),
+            GameMode::Sprint(lines) => format!("sprint{}", lines),
+        }
+    }
+
+    pub fn from_name(name: &str) -> Option<GameMode> {
+        GameMode::ALL.into_iter().find(|mode| mode.name() == name)
+    }
+
+    // lines that end the game, None if it only ends by topping out
+    pub fn target_lines(&self) -> Option<u32> {
+        match self {
+            GameMode::Endless => None,
+            GameMode::Sprint(lines) => Some(*lines),
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct GameOptions {
+    pub mode: GameMode,
+    pub start_level: u32,
+    pub board_width: u8,
+    pub board_height: u8, // visible rows, the hidden buffer is added on top
+    pub randomizer: RandomizerKind,
+    pub seed: u64,
+    pub lock_delay: Duration, // time a grounded block may still move before it locks
+}
+
+impl Default for GameOptions {
+    fn default() -> Self {
+        GameOptions {
+            mode: GameMode::Endless,
+            start_level: 1,
+            board_width: DEFAULT_WIDTH,
+            board_height: DEFAULT_HEIGHT,
+            randomizer: RandomizerKind::SevenBag,
+            seed: rand::random(),
+            lock_delay: LOCK_DELAY,
+        }
+    }
+}
+
+impl GameOptions {
+    // the options with the board size and start level moved into the ranges a game can be played with
+    pub fn clamped(&self) -> GameOptions {
+        GameOptions {
+            start_level: self.start_level.clamp(1, MAX_START_LEVEL),
+            board_width: self.board_width.clamp(MIN_SIZE, MAX_WIDTH),
+            board_height: self.board_height.clamp(MIN_SIZE, MAX_HEIGHT),
+            ..self.clone()
+        }
+    }
+}
+
+// what happened during a step, for the frontend to react to
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum GameEvent {
+    Locked { t_spin: TSpin },
+    LinesCleared { rows: Vec<usize>, points: u64 },
+    Split { lines: u32, time: Duration },
+    Finished { time: Duration }, // the mode's line target was reached
+    GameOver,
+}
+
+// the game as a pure state machine: inputs go in a logic tick at a time, events come out
 #[derive(Debug)]
 pub struct Game {
-    pub chunk: [Block; 7],
-    pub current_block: u8, // current block in chunk
-    lines: u8, // cleared lines
-    score: u16,
+    queue: BlockQueue,
+    current_block: Block,
+    hold: Option<Block>,
+    can_hold: bool, // false once the hold has been used until the next block locks
+    lines: u32, // cleared lines
+    scoring: Scoring,
+    last_kick: Option<usize>, // wall kick of the last rotation, None once the block moved another way
     board: Board,
+    options: GameOptions, // as played, kept for the replay. the seed deals the same blocks again
+    ticks: u64, // logic ticks played so far
+    elapsed: Duration, // logic time played so far
+    splits: Vec<Duration>, // elapsed time whenever another SPLIT_LINES lines were cleared
+    recording: Vec<(u64, Input)>, // every applied input and the tick it happened on
     game_over: bool,
-    counter: u8
+    finished: bool, // ended by reaching the line target rather than by topping out
+    paused: bool, // nothing moves and no time passes until the game is resumed
+    events: Vec<GameEvent>, // collected during the current step
+    gravity_timer: Duration, // time since the current block last fell
+    lock_timer: Option<Duration>, // time the current block has been resting on the stack
+    lock_resets: u8, // moves made since the block touched down at its lowest row
+    touched_down: bool, // the block has rested on the stack since reaching its lowest row
+    lowest_y: i16, // lowest row reached by the current block, reaching a new one refills the resets
+}
+
+impl Default for Game {
+    fn default() -> Self {
+        Self::new()
+    }
 }
 
 impl Game {
     pub fn new() -> Game {
+        Game::with_options(GameOptions::default())
+    }
+
+    pub fn with_options(options: GameOptions) -> Game {
+        let options = options.clamped();
+        let board = Board::with_size(options.board_width, options.board_height);
+        let mut queue = BlockQueue::new(options.randomizer.create(options.seed));
+        let mut current_block = queue.next_block();
+        current_block.reset(&board);
+
         Game {
-            chunk: new_chunk(),
-            current_block: 0,
+            lowest_y: current_block.y(),
+            current_block,
+            queue,
+            hold: None,
+            can_hold: true,
             lines: 0,
-            score: 0,
-            board: Board::new(),
+            scoring: Scoring::new(),
+            last_kick: None,
+            board,
+            options,
+            ticks: 0,
+            elapsed: Duration::ZERO,
+            splits: Vec::new(),
+            recording: Vec::new(),
             game_over: false,
-            counter: 0,
+            finished: false,
+            paused: false,
+            events: Vec::new(),
+            gravity_timer: Duration::ZERO,
+            lock_timer: None,
+            lock_resets: 0,
+            touched_down: false,
         }
     }
 
-    pub fn handle_falling(&mut self, stdout: &Stdout) {
-        self.counter += 1;
-        if self.counter == 2 {
-            self.clear(stdout);
-            if self.chunk[self.current_block as usize].y < self.board.height - 3 {
-                self.chunk[self.current_block as usize].y += 1;
-            } else {
-                // solidify block
-                self.place_current_block();
-                
-                self.current_block += 1;
-
-                if self.current_block >= 7 {
-                    self.current_block = 0;
-                    self.chunk = new_chunk();
-                }
-            }
-            self.counter = 0;
-            self.render(stdout)
+    // apply the inputs, then advance the game by one logic tick. a paused game ignores both.
+    // the tick is fixed so a replay, which only records ticks, plays out the same
+    pub fn step(&mut self, inputs: &[Input]) -> Vec<GameEvent> {
+        if self.paused {
+            return Vec::new();
+        }
+
+        for &input in inputs {
+            self.handle_input(input);
         }
+        self.handle_falling();
+
+        std::mem::take(&mut self.events)
     }
 
-    pub fn update(&mut self, stdout: &Stdout) {
+    // advance gravity and the lock delay by one logic tick
+    fn handle_falling(&mut self) {
         if self.game_over {
             return;
+        }
+        self.ticks += 1;
+        self.elapsed += TICK;
+
+        let gravity = gravity(self.level());
+        if gravity.is_zero() {
+            // 20G, the block reaches the stack within a single tick
+            let block = &mut self.current_block;
+            let landing_y = block.drop_y(&self.board);
+            if landing_y > block.y() && block.move_position(&self.board, block.x(), landing_y) {
+                self.block_fell();
+            }
         } else {
-            if self.current_block > 7 {
-                self.current_block = 0;
-                self.chunk = new_chunk();
+            self.gravity_timer += TICK;
+            while self.gravity_timer >= gravity {
+                self.gravity_timer -= gravity;
+                let block = &mut self.current_block;
+                if block.move_position(&self.board, block.x(), block.y() + 1) {
+                    self.block_fell();
+                }
             }
+        }
 
-            self.render(stdout);
+        // lock the block once it has rested on the stack for the whole lock delay,
+        // or as soon as it lands again once its moves have used up every reset
+        if self.is_grounded() {
+            self.touched_down = true;
+            let rested = match self.lock_timer {
+                None if self.lock_resets >= MAX_LOCK_RESETS => self.options.lock_delay,
+                timer => timer.unwrap_or(Duration::ZERO) + TICK,
+            };
+            if rested >= self.options.lock_delay {
+                self.lock_current_block();
+            } else {
+                self.lock_timer = Some(rested);
+            }
+        } else {
+            self.lock_timer = None;
         }
     }
 
-    pub fn render(&self, mut stdout: &Stdout) {
-        for y in 0..self.board.board_map.len() {
-            for x in 0..self.board.board_map[y].len() {
-                if self.board.board_map[y][x] != 0 {
-                    execute!(stdout, MoveTo(x as u16 * 2, y as u16), SetForegroundColor(get_color(self.board.board_map[y][x])), Print("██"), ResetColor).unwrap();
-                } 
-            }
-            execute!(stdout, MoveTo(0, y as u16)).unwrap();
+    fn is_grounded(&self) -> bool {
+        let block = &self.current_block;
+        !block.test_position(&self.board, block.state(), block.x(), block.y() + 1)
+    }
+
+    fn block_fell(&mut self) {
+        self.last_kick = None;
+        if self.current_block.y() > self.lowest_y {
+            self.lowest_y = self.current_block.y();
+            self.lock_resets = 0;
+            self.touched_down = false;
         }
+    }
 
-        // print current active block
-        for x in 0..4 {
-            for y in 0..4 {
-                if self.chunk[self.current_block as usize].shape[y][x] != 0 && self.board.board_map[y + self.chunk[self.current_block as usize].y as usize][x + self.chunk[self.current_block as usize].x as usize] == 0 {
-                    execute!(stdout, MoveTo((x + self.chunk[self.current_block as usize].x as usize) as u16 * 2 + MARGIN as u16, (y + self.chunk[self.current_block as usize].y as usize) as u16)).unwrap();
-                    execute!(stdout, SetForegroundColor(get_color(self.chunk[self.current_block as usize].shape[y][x])), Print("██"), ResetColor).unwrap();
-                }
-            }
+    // every successful move or rotation after touching down counts, on the stack or lifted off it.
+    // the first MAX_LOCK_RESETS of them restart the lock delay
+    fn block_moved(&mut self) {
+        if !self.touched_down || self.lock_resets >= MAX_LOCK_RESETS {
+            return;
+        }
+
+        self.lock_resets += 1;
+        if self.lock_timer.is_some() {
+            self.lock_timer = Some(Duration::ZERO);
         }
     }
 
-    pub fn init(&self, mut stdout: &Stdout) {
-        for y in 0..self.board.height {
-            if y + 1 < self.board.height {
-                execute!(stdout, MoveTo(0, y as u16), Print("<>")).unwrap();
-                execute!(stdout, MoveTo(self.board.width as u16 * 2 + MARGIN as u16, y as u16), Print("<>")).unwrap();
-            } else {
-                for x in 0..(self.board.width + MARGIN) {
-                    execute!(stdout, MoveTo(x as u16 * 2, y as u16), Print("<>")).unwrap();
-                }
-            }
-        } 
+    fn reset_lock_state(&mut self) {
+        self.lock_timer = None;
+        self.lock_resets = 0;
+        self.touched_down = false;
+        self.lowest_y = self.current_block.y();
     }
 
-    fn clear(&self, mut stdout: &Stdout) {
-        for x in 0..4 {
-            for y in 0..4 {
-                if self.chunk[self.current_block as usize].shape[y][x] != 0 && self.board.board_map[y + self.chunk[self.current_block as usize].y as usize][x + self.chunk[self.current_block as usize].x as usize] == 0 {
-                    execute!(stdout, MoveTo((x + self.chunk[self.current_block as usize].x as usize) as u16 * 2 + MARGIN as u16, (y + self.chunk[self.current_block as usize].y as usize) as u16)).unwrap();
-                    execute!(stdout, Print("  ")).unwrap();
-                }
-            }
+    // solidify the current block and bring in the next one
+    fn lock_current_block(&mut self) {
+        self.place_current_block();
+        self.can_hold = true;
+
+        if !self.game_over {
+            self.next_block();
         }
     }
 
-    pub fn place_current_block(&mut self) {
-        let current_block = &mut self.chunk[self.current_block as usize];
-        let x = current_block.x as usize;
-        let y = current_block.y as usize;
+    pub fn level(&self) -> u32 {
+        level_for(self.options.start_level, self.lines)
+    }
+
+    pub fn seed(&self) -> u64 {
+        self.options.seed
+    }
 
-        for (block_y, row) in current_block.shape.iter().enumerate() {
-            for (block_x, &value) in row.iter().enumerate() {
-                if value != 0 {
-                    let board_x = x + block_x;
-                    let board_y = y + block_y;
-                    self.board.set_position(board_x, board_y, value);
-                }
+    pub fn ticks(&self) -> u64 {
+        self.ticks
+    }
+
+    pub fn mode(&self) -> GameMode {
+        self.options.mode
+    }
+
+    // time played, a whole number of logic ticks. pauses do not count, and neither does
+    // time the frontend skipped instead of catching up, the game stood still then
+    pub fn elapsed(&self) -> Duration {
+        self.elapsed
+    }
+
+    pub fn splits(&self) -> &[Duration] {
+        &self.splits
+    }
+
+    // the recorded game so far, playing it back reproduces this game exactly
+    pub fn replay(&self) -> Replay {
+        Replay {
+            options: self.options.clone(),
+            inputs: self.recording.clone(),
+        }
+    }
+
+    pub fn is_game_over(&self) -> bool {
+        self.game_over
+    }
+
+    pub fn is_finished(&self) -> bool {
+        self.finished
+    }
+
+    // a finished game cannot be paused
+    pub fn pause(&mut self) {
+        self.paused = !self.game_over;
+    }
+
+    pub fn resume(&mut self) {
+        self.paused = false;
+    }
+
+    pub fn is_paused(&self) -> bool {
+        self.paused
+    }
+
+    // take the next block from the queue, ending the game if it cannot spawn (block out)
+    fn next_block(&mut self) {
+        self.current_block = self.queue.next_block();
+        self.current_block.reset(&self.board);
+        self.reset_lock_state();
+        self.check_block_out();
+    }
+
+    // block out: the block that just spawned overlaps the stack
+    fn check_block_out(&mut self) {
+        let block = &self.current_block;
+        if !block.test_position(&self.board, block.state(), block.x(), block.y()) {
+            self.end_game();
+        }
+    }
+
+    pub fn current_block(&self) -> &Block {
+        &self.current_block
+    }
+
+    // the next n blocks in spawn order, up to the queue's preview size
+    pub fn next_blocks(&self, n: usize) -> impl Iterator<Item = &Block> {
+        self.queue.peek(n)
+    }
+
+    pub fn board(&self) -> &Board {
+        &self.board
+    }
+
+    pub fn hold(&self) -> Option<&Block> {
+        self.hold.as_ref()
+    }
+
+    pub fn can_hold(&self) -> bool {
+        self.can_hold
+    }
+
+    pub fn lines(&self) -> u32 {
+        self.lines
+    }
+
+    pub fn scoring(&self) -> &Scoring {
+        &self.scoring
+    }
+
+    // the current block moved down to where it would land
+    pub fn ghost_block(&self) -> Block {
+        let mut ghost = self.current_block.clone();
+        ghost.move_position(&self.board, ghost.x(), ghost.drop_y(&self.board));
+
+        ghost
+    }
+
+    fn place_current_block(&mut self) {
+        let t_spin = self.t_spin();
+        let mut lock_out = true;
+
+        for (x, y, value) in self.current_block.cells() {
+            // cells above the top row have nowhere to go
+            if y >= 0 {
+                self.board.set_position(x as usize, y as usize, value);
             }
+
+            // lock out: the block settled entirely above the visible field
+            if !self.board.is_hidden_row(y) {
+                lock_out = false;
+            }
+        }
+
+        self.events.push(GameEvent::Locked { t_spin });
+        if lock_out {
+            self.end_game();
+        }
+
+        let level = self.level();
+        let rows = self.board.clear_completed_rows();
+        let cleared = rows.len();
+        self.lines += cleared as u32;
+        let points = self.scoring.lock(cleared, t_spin, cleared > 0 && self.board.is_empty(), level);
+        if cleared > 0 {
+            self.events.push(GameEvent::LinesCleared { rows, points });
+        }
+
+        // a split for every multiple of SPLIT_LINES the clear passed
+        for lines in (self.lines - cleared as u32 + 1)..=self.lines {
+            if lines % SPLIT_LINES == 0 {
+                self.splits.push(self.elapsed);
+                self.events.push(GameEvent::Split { lines, time: self.elapsed });
+            }
+        }
+
+        if self.options.mode.target_lines().is_some_and(|target| self.lines >= target) && !self.game_over {
+            self.game_over = true;
+            self.finished = true;
+            self.events.push(GameEvent::Finished { time: self.elapsed });
+        }
+    }
+
+    fn end_game(&mut self) {
+        if !self.game_over {
+            self.game_over = true;
+            self.events.push(GameEvent::GameOver);
+        }
+    }
+
+    // 3-corner rule: a T block locked right after a rotation with at least 3 of the
+    // 4 cells diagonal to its center filled. it is a mini unless both corners on the
+    // pointing side are filled or the last kick of the table was needed
+    fn t_spin(&self) -> TSpin {
+        let block = &self.current_block;
+        let Some(kick) = self.last_kick else {
+            return TSpin::None;
+        };
+        if block.kind() != BlockType::T {
+            return TSpin::None;
+        }
+
+        // the center of every T state sits at (1, 1) of the shape
+        let filled = |dx: i16, dy: i16| self.board.is_occupied(block.x() + dx, block.y() + dy);
+        let corners = [filled(0, 0), filled(2, 0), filled(2, 2), filled(0, 2)];
+        if corners.iter().filter(|&&corner| corner).count() < 3 {
+            return TSpin::None;
+        }
+
+        // corners clockwise from the top left, the T points up, right, down, left in states 0 to 3
+        let state = block.state() as usize;
+        let front = corners[state] && corners[(state + 1) % 4];
+        if front || kick == 4 {
+            TSpin::Full
+        } else {
+            TSpin::Mini
+        }
+    }
+
+    // swap the current block with the held one, only once until the next block locks.
+    // returns whether the blocks were swapped
+    fn hold_current_block(&mut self) -> bool {
+        if !self.can_hold {
+            return false;
         }
+        self.can_hold = false;
 
-        // self.board.clear_completed_rows();
+        let mut current = self.current_block.clone();
+        current.reset(&self.board);
 
-        current_block.x = STARTING_X;
-        current_block.y = 0;
-        current_block.current_state = 0;
-        current_block.shape = current_block.states[current_block.current_state as usize];
+        match self.hold.replace(current) {
+            Some(held) => {
+                self.current_block = held;
+                self.reset_lock_state();
+                self.check_block_out();
+            },
+            None => self.next_block(),
+        }
+
+        true
     }
 
-    pub fn handle_input(&mut self, input: Input, stdout: &Stdout) {
+    fn handle_input(&mut self, input: Input) {
+        if self.game_over {
+            return;
+        }
+        self.recording.push((self.ticks, input));
+
         match input {
             Input::Left => {
-                self.clear(stdout);
-                if self.chunk[self.current_block as usize].x > 0 {
-                    self.chunk[self.current_block as usize].move_position(&self.board, self.chunk[self.current_block as usize].x - 1, self.chunk[self.current_block as usize].y);
-                } 
+                if self.current_block.move_position(&self.board, self.current_block.x() - 1, self.current_block.y()) {
+                    self.last_kick = None;
+                    self.block_moved();
+                }
             },
             Input::Right => {
-                self.clear(stdout);
-                self.chunk[self.current_block as usize].move_position(&self.board, self.chunk[self.current_block as usize].x + 1, self.chunk[self.current_block as usize].y);
+                if self.current_block.move_position(&self.board, self.current_block.x() + 1, self.current_block.y()) {
+                    self.last_kick = None;
+                    self.block_moved();
+                }
             },
             Input::Clockwise => {
-                self.clear(stdout);
-                self.chunk[self.current_block as usize].rotate_right(&self.board);
+                if let Some(kick) = self.current_block.rotate_right(&self.board) {
+                    self.last_kick = Some(kick);
+                    self.block_moved();
+                }
             },
             Input::Counterclockwise => {
-                self.clear(stdout);
-                self.chunk[self.current_block as usize].rotate_left(&self.board);
+                if let Some(kick) = self.current_block.rotate_left(&self.board) {
+                    self.last_kick = Some(kick);
+                    self.block_moved();
+                }
             },
             Input::Drop => {
-                todo!();
+                let block = &mut self.current_block;
+                let landing_y = block.drop_y(&self.board);
+                let rows = (landing_y - block.y()) as u32;
+                if rows > 0 && block.move_position(&self.board, block.x(), landing_y) {
+                    self.block_fell();
+                }
+
+                self.scoring.hard_drop(rows);
+                self.lock_current_block();
+                self.gravity_timer = Duration::ZERO;
+            },
+            Input::Hold => {
+                // the swapped in block starts falling afresh, an unavailable hold changes nothing
+                if self.hold_current_block() {
+                    self.gravity_timer = Duration::ZERO;
+                }
+            },
+            Input::SoftDrop => {
+                let block = &mut self.current_block;
+                if block.move_position(&self.board, block.x(), block.y() + 1) {
+                    self.block_fell();
+                    self.scoring.soft_drop(1);
+                    // restart the gravity countdown so a held key keeps the block falling faster
+                    self.gravity_timer = Duration::ZERO;
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::block::placed_block;
+    use crate::clock::TICK_RATE;
+
+    fn game() -> Game {
+        Game::with_options(GameOptions { seed: 1, ..GameOptions::default() })
+    }
+
+    // rows from the bottom of the board up, '#' for a filled cell
+    fn fill(game: &mut Game, rows: &[&str]) {
+        let bottom = game.board.rows() as usize - 1;
+        for (i, row) in rows.iter().enumerate() {
+            for (x, cell) in row.chars().enumerate() {
+                game.board.set_position(x, bottom - i, (cell == '#') as u8);
+            }
+        }
+    }
+
+    fn place(game: &mut Game, kind: BlockType, state: u8, x: i16, y: i16) {
+        game.current_block = placed_block(kind, state, x, y);
+        game.reset_lock_state();
+    }
+
+    // whole ticks it takes for the duration to pass
+    fn ticks(duration: Duration) -> u64 {
+        (duration.as_secs_f64() * TICK_RATE as f64).ceil() as u64
+    }
+
+    fn locked(events: &[GameEvent]) -> Option<TSpin> {
+        events.iter().find_map(|event| match event {
+            GameEvent::Locked { t_spin } => Some(*t_spin),
+            _ => None,
+        })
+    }
+
+    #[test]
+    fn t_spin_with_both_front_corners_is_full() {
+        let mut game = game();
+        fill(&mut game, &["#.########", "...#######", "#........."]);
+        // pointing right next to the slot, turning clockwise points it down into it
+        place(&mut game, BlockType::T, 1, 0, 37);
+
+        let events = game.step(&[Input::Clockwise, Input::Drop]);
+        assert_eq!(locked(&events), Some(TSpin::Full));
+        assert!(events.contains(&GameEvent::LinesCleared { rows: vec![38, 39], points: 1200 }));
+    }
+
+    #[test]
+    fn t_spin_with_one_front_corner_is_mini() {
+        let mut game = game();
+        fill(&mut game, &["#..#######", "...#######", "#.#......."]);
+        place(&mut game, BlockType::T, 1, 0, 37);
+
+        let events = game.step(&[Input::Clockwise, Input::Drop]);
+        assert_eq!(locked(&events), Some(TSpin::Mini));
+        assert!(events.contains(&GameEvent::LinesCleared { rows: vec![38], points: 200 }));
+    }
+
+    #[test]
+    fn moving_after_the_rotation_is_no_t_spin() {
+        let mut game = game();
+        fill(&mut game, &["#.########", "...#######", "#........."]);
+        place(&mut game, BlockType::T, 1, 1, 36);
+
+        let events = game.step(&[Input::Left, Input::Clockwise, Input::Drop]);
+        assert_eq!(locked(&events), Some(TSpin::None));
+    }
+
+    #[test]
+    fn locking_above_the_visible_field_is_lock_out() {
+        let mut game = game();
+        // the top hidden row is filled under the block, away from where the next one spawns
+        for x in 0..3 {
+            game.board.set_position(x, 19, 1);
+        }
+        place(&mut game, BlockType::T, 0, 0, 17);
+
+        let events = game.step(&[Input::Drop]);
+        assert_eq!(locked(&events), Some(TSpin::None));
+        assert_eq!(events.last(), Some(&GameEvent::GameOver));
+        assert!(game.is_game_over());
+    }
+
+    #[test]
+    fn locking_partly_in_view_goes_on() {
+        let mut game = game();
+        for x in 0..3 {
+            game.board.set_position(x, 21, 1);
+        }
+        place(&mut game, BlockType::T, 0, 0, 19);
+
+        let events = game.step(&[Input::Drop]);
+        assert_eq!(locked(&events), Some(TSpin::None));
+        assert!(!game.is_game_over());
+    }
+
+    #[test]
+    fn spawning_into_the_stack_is_block_out() {
+        let mut game = game();
+        for x in 3..7 {
+            game.board.set_position(x, 20, 1);
+        }
+        // a vertical I down the right wall, the next block cannot spawn
+        place(&mut game, BlockType::I, 1, 7, 30);
+
+        let events = game.step(&[Input::Drop]);
+        assert_eq!(events.last(), Some(&GameEvent::GameOver));
+        assert!(game.is_game_over());
+        assert!(!game.board.is_empty());
+    }
+
+    #[test]
+    fn lock_resets_run_out() {
+        let mut game = game();
+        place(&mut game, BlockType::T, 0, 3, 38);
+
+        // rotating back and forth on the floor cannot keep the block up forever
+        let limit = MAX_LOCK_RESETS as u64 * 20 + 2 * ticks(LOCK_DELAY);
+        let mut ticks = 0;
+        loop {
+            let input: &[Input] = match ticks % 40 {
+                0 => &[Input::Clockwise],
+                20 => &[Input::Counterclockwise],
+                _ => &[],
+            };
+            if locked(&game.step(input)).is_some() {
+                break;
             }
+            ticks += 1;
+            assert!(ticks < limit, "still not locked after {} ticks", ticks);
+        }
+    }
+
+    #[test]
+    fn landing_again_without_resets_locks_at_once() {
+        let mut game = game();
+        place(&mut game, BlockType::T, 0, 3, 38);
+        game.step(&[]);
+        assert!(game.touched_down && game.lock_timer.is_some());
+
+        // as if the last reset was used to lift the block off the stack
+        game.lock_resets = MAX_LOCK_RESETS;
+        game.lock_timer = None;
+        let events = game.step(&[]);
+        assert_eq!(locked(&events), Some(TSpin::None));
+    }
+
+    #[test]
+    fn an_unavailable_hold_does_not_stop_gravity() {
+        let mut game = game();
+        game.step(&[Input::Hold]);
+        let y = game.current_block.y();
+
+        for _ in 0..2 * ticks(gravity(1)) {
+            game.step(&[Input::Hold]);
+        }
+        assert!(game.current_block.y() > y);
+    }
+
+    #[test]
+    fn sprint_finishes_at_the_target() {
+        let mut game = Game::with_options(GameOptions { mode: GameMode::Sprint(2), seed: 1, ..GameOptions::default() });
+        fill(&mut game, &["#########.", "#########."]);
+        place(&mut game, BlockType::I, 1, 7, 30);
+        for _ in 0..10 {
+            game.step(&[]);
         }
+
+        let events = game.step(&[Input::Drop]);
+        assert!(events.contains(&GameEvent::Finished { time: TICK * 10 }));
+        assert!(game.is_finished() && game.is_game_over());
     }
 }
\ No newline at end of file
@@ -1,8 +1,10 @@
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum Input {
     Left,
     Right,
     Clockwise,
     Counterclockwise,
     Drop,
-    // SoftDrop,
+    SoftDrop,
+    Hold,
 }
\ No newline at end of file
@@ -0,0 +1,352 @@
+use std::collections::HashMap;
+use std::io::Result;
+
+use crossterm::event::{KeyCode, KeyEvent, KeyModifiers};
+use retris::Input;
+
+use crate::storage::invalid;
+
+// everything a key can be bound to, game inputs plus the frontend's own controls.
+// menu actions only apply while a menu is open, so they may share keys with the others
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Action {
+    Left,
+    Right,
+    Clockwise,
+    Counterclockwise,
+    SoftDrop,
+    HardDrop,
+    Hold,
+    Restart,
+    Pause,
+    Quit,
+    MenuUp,
+    MenuDown,
+    MenuLeft,
+    MenuRight,
+    Select,
+    Back,
+}
+
+impl Action {
+    pub const ALL: [Action; 16] = [
+        Action::Left,
+        Action::Right,
+        Action::Clockwise,
+        Action::Counterclockwise,
+        Action::SoftDrop,
+        Action::HardDrop,
+        Action::Hold,
+        Action::Restart,
+        Action::Pause,
+        Action::Quit,
+        Action::MenuUp,
+        Action::MenuDown,
+        Action::MenuLeft,
+        Action::MenuRight,
+        Action::Select,
+        Action::Back,
+    ];
+
+    // the name used in the [keys] table of the config file
+    pub fn name(&self) -> &'static str {
+        match self {
+            Action::Left => "left",
+            Action::Right => "right",
+            Action::Clockwise => "rotate_right",
+            Action::Counterclockwise => "rotate_left",
+            Action::SoftDrop => "soft_drop",
+            Action::HardDrop => "hard_drop",
+            Action::Hold => "hold",
+            Action::Restart => "restart",
+            Action::Pause => "pause",
+            Action::Quit => "quit",
+            Action::MenuUp => "menu_up",
+            Action::MenuDown => "menu_down",
+            Action::MenuLeft => "menu_left",
+            Action::MenuRight => "menu_right",
+            Action::Select => "select",
+            Action::Back => "back",
+        }
+    }
+
+    pub fn from_name(name: &str) -> Option<Action> {
+        Action::ALL.into_iter().find(|action| action.name() == name)
+    }
+
+    // the game input this action feeds, None for frontend controls
+    pub fn input(&self) -> Option<Input> {
+        match self {
+            Action::Left => Some(Input::Left),
+            Action::Right => Some(Input::Right),
+            Action::Clockwise => Some(Input::Clockwise),
+            Action::Counterclockwise => Some(Input::Counterclockwise),
+            Action::SoftDrop => Some(Input::SoftDrop),
+            Action::HardDrop => Some(Input::Drop),
+            Action::Hold => Some(Input::Hold),
+            _ => None,
+        }
+    }
+
+    pub fn is_menu(&self) -> bool {
+        matches!(
+            self,
+            Action::MenuUp | Action::MenuDown | Action::MenuLeft | Action::MenuRight | Action::Select | Action::Back
+        )
+    }
+}
+
+// built-in layouts, the config file can start from one and rebind single actions
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Preset {
+    Vim,
+    Arrows,
+    Wasd,
+    Guideline,
+}
+
+impl Preset {
+    pub const ALL: [Preset; 4] = [Preset::Vim, Preset::Arrows, Preset::Wasd, Preset::Guideline];
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            Preset::Vim => "vim",
+            Preset::Arrows => "arrows",
+            Preset::Wasd => "wasd",
+            Preset::Guideline => "guideline",
+        }
+    }
+
+    pub fn from_name(name: &str) -> Option<Preset> {
+        Preset::ALL.into_iter().find(|preset| preset.name() == name)
+    }
+
+    fn bindings(&self) -> &'static [(Action, &'static [&'static str])] {
+        match self {
+            Preset::Vim => &[
+                (Action::Left, &["h"]),
+                (Action::Right, &["l"]),
+                (Action::Clockwise, &["k"]),
+                (Action::Counterclockwise, &["j"]),
+                (Action::SoftDrop, &["down"]),
+                (Action::HardDrop, &["space"]),
+                (Action::Hold, &["c"]),
+                (Action::Restart, &["r"]),
+                (Action::Pause, &["p", "esc"]),
+                (Action::Quit, &["q", "ctrl+c"]),
+                (Action::MenuUp, &["k", "up"]),
+                (Action::MenuDown, &["j", "down"]),
+                (Action::MenuLeft, &["h", "left"]),
+                (Action::MenuRight, &["l", "right"]),
+                (Action::Select, &["enter", "space"]),
+                (Action::Back, &["esc", "p"]),
+            ],
+            Preset::Arrows => &[
+                (Action::Left, &["left"]),
+                (Action::Right, &["right"]),
+                (Action::Clockwise, &["up"]),
+                (Action::Counterclockwise, &["z"]),
+                (Action::SoftDrop, &["down"]),
+                (Action::HardDrop, &["space"]),
+                (Action::Hold, &["c"]),
+                (Action::Restart, &["r"]),
+                (Action::Pause, &["p", "esc"]),
+                (Action::Quit, &["q", "ctrl+c"]),
+                (Action::MenuUp, &["up"]),
+                (Action::MenuDown, &["down"]),
+                (Action::MenuLeft, &["left"]),
+                (Action::MenuRight, &["right"]),
+                (Action::Select, &["enter", "space"]),
+                (Action::Back, &["esc", "p"]),
+            ],
+            Preset::Wasd => &[
+                (Action::Left, &["a"]),
+                (Action::Right, &["d"]),
+                (Action::Clockwise, &["k"]),
+                (Action::Counterclockwise, &["j"]),
+                (Action::SoftDrop, &["s"]),
+                (Action::HardDrop, &["w"]),
+                (Action::Hold, &["l"]),
+                (Action::Restart, &["r"]),
+                (Action::Pause, &["p", "esc"]),
+                (Action::Quit, &["q", "ctrl+c"]),
+                (Action::MenuUp, &["w", "up"]),
+                (Action::MenuDown, &["s", "down"]),
+                (Action::MenuLeft, &["a", "left"]),
+                (Action::MenuRight, &["d", "right"]),
+                (Action::Select, &["enter", "space"]),
+                (Action::Back, &["esc", "p"]),
+            ],
+            // the guideline layout, including its number pad keys
+            Preset::Guideline => &[
+                (Action::Left, &["left", "4"]),
+                (Action::Right, &["right", "6"]),
+                (Action::Clockwise, &["up", "x", "1", "5", "9"]),
+                (Action::Counterclockwise, &["z", "3", "7"]),
+                (Action::SoftDrop, &["down", "2"]),
+                (Action::HardDrop, &["space", "8"]),
+                (Action::Hold, &["c", "C", "0"]),
+                (Action::Restart, &["r"]),
+                (Action::Pause, &["esc", "f1"]),
+                (Action::Quit, &["ctrl+c"]),
+                (Action::MenuUp, &["up", "8"]),
+                (Action::MenuDown, &["down", "2"]),
+                (Action::MenuLeft, &["left", "4"]),
+                (Action::MenuRight, &["right", "6"]),
+                (Action::Select, &["enter", "space"]),
+                (Action::Back, &["esc", "f1"]),
+            ],
+        }
+    }
+}
+
+// maps key combinations to actions, an action may have any number of keys
+#[derive(Debug, Clone)]
+pub struct Keymap {
+    bindings: HashMap<(KeyCode, KeyModifiers), Vec<Action>>,
+}
+
+impl Default for Keymap {
+    fn default() -> Self {
+        Keymap::preset(Preset::Vim)
+    }
+}
+
+impl Keymap {
+    pub fn preset(preset: Preset) -> Keymap {
+        let mut keymap = Keymap { bindings: HashMap::new() };
+        for &(action, keys) in preset.bindings() {
+            for key in keys {
+                let key = parse_key(key).expect("preset keys are valid");
+                keymap.bindings.entry(key).or_default().push(action);
+            }
+        }
+
+        keymap
+    }
+
+    // replace every key of an action with the given ones
+    pub fn bind(&mut self, action: Action, keys: &[&str]) -> Result<()> {
+        let keys = keys
+            .iter()
+            .map(|key| parse_key(key).ok_or_else(|| invalid(format!("unknown key \"{}\" for {}", key, action.name()))))
+            .collect::<Result<Vec<_>>>()?;
+
+        for bound in self.bindings.values_mut() {
+            bound.retain(|&bound| bound != action);
+        }
+        for key in keys {
+            self.bindings.entry(key).or_default().push(action);
+        }
+
+        Ok(())
+    }
+
+    // the action for the key during play
+    pub fn action(&self, event: &KeyEvent) -> Option<Action> {
+        self.find(event, |action| !action.is_menu())
+    }
+
+    // the action for the key while a menu is open
+    pub fn menu_action(&self, event: &KeyEvent) -> Option<Action> {
+        self.find(event, |action| action.is_menu())
+    }
+
+    fn find(&self, event: &KeyEvent, matches: impl Fn(&Action) -> bool) -> Option<Action> {
+        let bound = self.bindings.get(&normalize(event.code, event.modifiers))?;
+        bound.iter().copied().find(matches)
+    }
+}
+
+// shift is part of the character itself, so "H" and "shift+h" are the same key
+fn normalize(code: KeyCode, modifiers: KeyModifiers) -> (KeyCode, KeyModifiers) {
+    match code {
+        KeyCode::Char(c) if modifiers.contains(KeyModifiers::SHIFT) => {
+            (KeyCode::Char(c.to_ascii_uppercase()), modifiers - KeyModifiers::SHIFT)
+        }
+        _ => (code, modifiers),
+    }
+}
+
+// keys are written as a name or a single character, optionally prefixed by modifiers: "ctrl+x", "space", "f1"
+fn parse_key(text: &str) -> Option<(KeyCode, KeyModifiers)> {
+    let (prefix, key) = match text.rsplit_once('+') {
+        Some((prefix, key)) if !key.is_empty() => (prefix, key),
+        _ => ("", text),
+    };
+
+    let mut modifiers = KeyModifiers::NONE;
+    for modifier in prefix.split('+').filter(|modifier| !modifier.is_empty()) {
+        modifiers |= match modifier.to_ascii_lowercase().as_str() {
+            "ctrl" | "control" => KeyModifiers::CONTROL,
+            "alt" => KeyModifiers::ALT,
+            "shift" => KeyModifiers::SHIFT,
+            _ => return None,
+        };
+    }
+
+    let mut chars = key.chars();
+    let code = match (chars.next(), chars.next()) {
+        (Some(c), None) => KeyCode::Char(c),
+        _ => match key.to_ascii_lowercase().as_str() {
+            "space" => KeyCode::Char(' '),
+            "esc" | "escape" => KeyCode::Esc,
+            "enter" | "return" => KeyCode::Enter,
+            "tab" => KeyCode::Tab,
+            "backspace" => KeyCode::Backspace,
+            "left" => KeyCode::Left,
+            "right" => KeyCode::Right,
+            "up" => KeyCode::Up,
+            "down" => KeyCode::Down,
+            "home" => KeyCode::Home,
+            "end" => KeyCode::End,
+            "pageup" => KeyCode::PageUp,
+            "pagedown" => KeyCode::PageDown,
+            "insert" => KeyCode::Insert,
+            "delete" => KeyCode::Delete,
+            name => KeyCode::F(name.strip_prefix('f')?.parse().ok().filter(|n| (1..=12).contains(n))?),
+        },
+    };
+
+    Some(normalize(code, modifiers))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn modifiers_come_before_the_key() {
+        assert_eq!(parse_key("ctrl+x"), Some((KeyCode::Char('x'), KeyModifiers::CONTROL)));
+        assert_eq!(parse_key("Ctrl+Alt+left"), Some((KeyCode::Left, KeyModifiers::CONTROL | KeyModifiers::ALT)));
+        // a plus on its own is the key, not a separator
+        assert_eq!(parse_key("+"), Some((KeyCode::Char('+'), KeyModifiers::NONE)));
+    }
+
+    #[test]
+    fn shift_is_part_of_the_character() {
+        assert_eq!(parse_key("shift+c"), parse_key("C"));
+        assert_ne!(parse_key("shift+c"), parse_key("c"));
+
+        // terminals report shifted letters as the capital together with shift
+        let keymap = Keymap::preset(Preset::Guideline);
+        let event = KeyEvent::new(KeyCode::Char('C'), KeyModifiers::SHIFT);
+        assert_eq!(keymap.action(&event), Some(Action::Hold));
+    }
+
+    #[test]
+    fn function_keys_go_up_to_f12() {
+        assert_eq!(parse_key("f1"), Some((KeyCode::F(1), KeyModifiers::NONE)));
+        assert_eq!(parse_key("F12"), Some((KeyCode::F(12), KeyModifiers::NONE)));
+        assert_eq!(parse_key("f0"), None);
+        assert_eq!(parse_key("f13"), None);
+    }
+
+    #[test]
+    fn unknown_keys_are_rejected() {
+        for key in ["", "ctrl+", "meta+x", "spacebar", "fx"] {
+            assert_eq!(parse_key(key), None, "{:?}", key);
+        }
+        assert!(Keymap::default().bind(Action::Hold, &["c", "hyper+c"]).is_err());
+    }
+}
\ No newline at end of file
@@ -0,0 +1,20 @@
+use std::time::Duration;
+
+pub const LINES_PER_LEVEL: u32 = 10;
+pub const MAX_START_LEVEL: u32 = 15;
+const TWENTY_G_LEVEL: u32 = 20; // from this level on blocks drop to the stack instantly
+
+pub fn level_for(start_level: u32, lines: u32) -> u32 {
+    start_level + lines / LINES_PER_LEVEL
+}
+
+// time for a block to fall one row at the given level, following the guideline curve
+// (0.8 - (level - 1) * 0.007) ^ (level - 1) seconds. zero means 20G
+pub fn gravity(level: u32) -> Duration {
+    if level >= TWENTY_G_LEVEL {
+        return Duration::ZERO;
+    }
+
+    let steps = level.max(1) as f64 - 1.0;
+    Duration::from_secs_f64((0.8 - steps * 0.007).powf(steps))
+}
\ No newline at end of file
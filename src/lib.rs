@@ -0,0 +1,27 @@
+//! The retris engine: board, blocks, randomizers, scoring and the `Game` state
+//! machine, without any terminal code. The `retris` binary is one frontend for it.
+
+pub mod autoshift;
+pub mod board;
+pub mod block;
+pub mod clock;
+pub mod game;
+pub mod input;
+pub mod level;
+pub mod queue;
+pub mod randomizer;
+pub mod render;
+pub mod replay;
+pub mod scoring;
+pub mod softdrop;
+
+pub use autoshift::AutoShift;
+pub use board::Board;
+pub use block::{Block, BlockType};
+pub use game::{Game, GameEvent, GameMode, GameOptions};
+pub use input::Input;
+pub use randomizer::{Randomizer, RandomizerKind};
+pub use render::Renderer;
+pub use replay::Replay;
+pub use scoring::{Scoring, TSpin};
+pub use softdrop::SoftDrop;
\ No newline at end of file
@@ -1,96 +1,507 @@
-// Keybinds:
+// Keybinds (vim preset, see keymap.rs for the others and config.rs to change them):
 // rotate right: k
 // rotate left: j
 // right: l
 // left: h
+// soft drop: down arrow
 // hard drop: space
-// quit: q or esc
+// hold: c
+// restart: r
+// pause menu: p or esc
+// quit: q
 
-pub mod board;
-pub mod block;
-pub mod game;
-pub mod input;
-pub mod colors;
+mod colors;
+mod config;
+mod controls;
+mod keymap;
+mod menu;
+mod scores;
+mod storage;
+mod terminal;
+mod timer;
 
-use game::Game;
-use input::Input;
+use retris::board::{MAX_HEIGHT, MAX_WIDTH, MIN_SIZE};
+use retris::clock::{GameClock, TICK};
+use retris::level::MAX_START_LEVEL;
+use retris::{AutoShift, Game, GameEvent, GameMode, GameOptions, RandomizerKind, Renderer, Replay};
+use config::Config;
+use controls::Controls;
+use keymap::Action;
+use menu::{high_scores_menu, Menu, MenuEvent, Setting, TitleItem};
+use scores::HighScores;
+use terminal::TerminalRenderer;
+use timer::SprintTimer;
 
 use crossterm::{cursor, execute};
-use crossterm::event::{poll, read, Event, KeyCode, KeyEvent, KeyModifiers, KeyEventKind};
+use crossterm::event::{
+    poll, read, Event, KeyCode, KeyEvent, KeyEventKind, KeyboardEnhancementFlags, PopKeyboardEnhancementFlags,
+    PushKeyboardEnhancementFlags,
+};
 
-use crossterm::terminal::{disable_raw_mode, enable_raw_mode, EnterAlternateScreen, LeaveAlternateScreen};
-use std::{time::Duration, io::stdout};
+use crossterm::terminal::{
+    disable_raw_mode, enable_raw_mode, supports_keyboard_enhancement, EnterAlternateScreen, LeaveAlternateScreen,
+};
+use std::io::{self, stdout};
+use std::path::PathBuf;
+use std::process::exit;
+
+const USAGE: &str = "usage: retris [--mode endless|sprint20|sprint40|sprint100] [--level <1-15>] [--randomizer 7bag|14bag|random|tgm|nes] [--width <4-20>] [--height <4-40>] [--seed <u64>] [--record <file>] [--replay <file>]";
+
+const FAST_FORWARD: u32 = 4; // replay speed multiplier while fast forwarding
+
+struct Args {
+    options: GameOptions,
+    seeded: bool, // the seed was fixed on the command line
+    record: Option<PathBuf>,
+    replay: Option<PathBuf>,
+}
+
+fn parse_args() -> Args {
+    let mut options = GameOptions::default();
+    let mut seeded = false;
+    let mut record = None;
+    let mut replay = None;
+    let mut args = std::env::args().skip(1);
+
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--mode" => {
+                match args.next().as_deref().and_then(GameMode::from_name) {
+                    Some(mode) => options.mode = mode,
+                    None => {
+                        eprintln!("{}", USAGE);
+                        exit(1);
+                    }
+                }
+            }
+            "--level" => {
+                let Some(level) = args.next().and_then(|level| level.parse().ok()).filter(|level| (1..=MAX_START_LEVEL).contains(level)) else {
+                    eprintln!("{}", USAGE);
+                    exit(1);
+                };
+                options.start_level = level;
+            }
+            "--randomizer" => {
+                match args.next().as_deref().and_then(RandomizerKind::from_name) {
+                    Some(kind) => options.randomizer = kind,
+                    None => {
+                        eprintln!("{}", USAGE);
+                        exit(1);
+                    }
+                }
+            }
+            "--width" | "--height" => {
+                let max = if arg == "--width" { MAX_WIDTH } else { MAX_HEIGHT };
+                let Some(size) = args.next().and_then(|size| size.parse().ok()).filter(|size| (MIN_SIZE..=max).contains(size)) else {
+                    eprintln!("{}", USAGE);
+                    exit(1);
+                };
+                if arg == "--width" {
+                    options.board_width = size;
+                } else {
+                    options.board_height = size;
+                }
+            }
+            "--seed" => {
+                match args.next().and_then(|seed| seed.parse().ok()) {
+                    Some(seed) => {
+                        options.seed = seed;
+                        seeded = true;
+                    }
+                    None => {
+                        eprintln!("{}", USAGE);
+                        exit(1);
+                    }
+                }
+            }
+            "--record" | "--replay" => {
+                let Some(path) = args.next() else {
+                    eprintln!("{}", USAGE);
+                    exit(1);
+                };
+                if arg == "--record" {
+                    record = Some(PathBuf::from(path));
+                } else {
+                    replay = Some(PathBuf::from(path));
+                }
+            }
+            _ => {
+                eprintln!("{}", USAGE);
+                exit(1);
+            }
+        }
+    }
+
+    Args { options, seeded, record, replay }
+}
 
 fn main() {
+    let mut args = parse_args();
+    let mut config = Config::load().unwrap_or_else(|err| {
+        let path = Config::path().unwrap_or_default();
+        eprintln!("could not load config {}: {}", path.display(), err);
+        exit(1);
+    });
+    let mut scores = HighScores::load().unwrap_or_else(|err| {
+        let path = HighScores::path().unwrap_or_default();
+        eprintln!("could not load high scores {}: {}", path.display(), err);
+        exit(1);
+    });
+    // load the replay before taking over the terminal so errors stay readable
+    let replay = args.replay.as_ref().map(|path| {
+        Replay::load(path).unwrap_or_else(|err| {
+            eprintln!("could not load replay {}: {}", path.display(), err);
+            exit(1);
+        })
+    });
+
     let mut stdout = stdout();
     enable_raw_mode().unwrap();
 
     execute!(stdout, EnterAlternateScreen, cursor::MoveTo(0, 0)).unwrap();
-    let mut game = Game::new();
+    execute!(stdout, cursor::Hide).unwrap();
+
+    // ask for key release events so held keys can be tracked, not every terminal has them
+    let release_events = supports_keyboard_enhancement().unwrap_or(false);
+    if release_events {
+        let flags = KeyboardEnhancementFlags::DISAMBIGUATE_ESCAPE_CODES
+            | KeyboardEnhancementFlags::REPORT_EVENT_TYPES
+            | KeyboardEnhancementFlags::REPORT_ALL_KEYS_AS_ESCAPE_CODES;
+        execute!(stdout, PushKeyboardEnhancementFlags(flags)).unwrap();
+    }
 
-    execute!(stdout, cursor::Hide);
+    let mut renderer = TerminalRenderer::new(std::io::stdout());
+    let game = match &replay {
+        Some(replay) => Some(play_replay(replay, &mut renderer)),
+        None => {
+            let mut controls = Controls::new(config.keymap(), AutoShift::new(config.das, config.arr), release_events);
+            title(&mut args, &mut config, &mut scores, &mut controls, &mut renderer)
+        }
+    };
 
-    game.init(&stdout);
+    if release_events {
+        execute!(stdout, PopKeyboardEnhancementFlags).unwrap();
+    }
+    execute!(stdout, LeaveAlternateScreen).unwrap();
+    disable_raw_mode().unwrap();
+
+    // nothing to report when no game was played
+    let Some(game) = game else {
+        return;
+    };
+
+    if let Some(path) = &args.record {
+        if let Err(err) = game.replay().save(path) {
+            eprintln!("could not save replay {}: {}", path.display(), err);
+        }
+    }
+
+    println!("seed: {}", game.seed());
+}
+
+// the screens reachable from the title menu
+enum TitleScreen {
+    Main,
+    HighScores(Menu),
+    Settings(Menu, Config), // with the config as it was when the menu opened
+}
+
+// the title menu until it is quit, returns the last game played
+fn title(
+    args: &mut Args,
+    config: &mut Config,
+    scores: &mut HighScores,
+    controls: &mut Controls,
+    renderer: &mut TerminalRenderer,
+) -> Option<Game> {
+    let mut main_menu = TitleItem::menu(&args.options);
+    let mut screen = TitleScreen::Main;
+    let mut last_game = None;
 
     loop {
-        game.update(&stdout);
-        game.handle_falling(&stdout);
-
-        if poll(Duration::from_millis(500)).unwrap() {
-            if let Ok(event) = read() {
-                match event {
-                    // q to exit
-                    Event::Key(KeyEvent {
-                        code: KeyCode::Char('q'),
-                        modifiers: KeyModifiers::NONE,
-                        kind: KeyEventKind::Press,
-                        ..
-                    }) => {
-                        break;
-                    }
-                    // left
-                    Event::Key(KeyEvent {
-                        code: KeyCode::Char('h'),
-                        modifiers: KeyModifiers::NONE,
-                        kind: KeyEventKind::Press,
-                        ..
-                    }) => {
-                        game.handle_input(Input::Left, &stdout);
-                    }
-                    // right
-                    Event::Key(KeyEvent {
-                        code: KeyCode::Char('l'),
-                        modifiers: KeyModifiers::NONE,
-                        kind: KeyEventKind::Press,
-                        ..
-                    }) => {
-                        game.handle_input(Input::Right, &stdout);
+        let menu = match &mut screen {
+            TitleScreen::Main => &mut main_menu,
+            TitleScreen::HighScores(menu) | TitleScreen::Settings(menu, _) => menu,
+        };
+        renderer.render_title(menu);
+
+        let Ok(Event::Key(event)) = read() else {
+            continue;
+        };
+        let Some(action) = controls.menu_key(&event) else {
+            continue;
+        };
+        if action == Action::Quit {
+            break;
+        }
+        let Some(choice) = menu.navigate(action) else {
+            continue;
+        };
+
+        match &mut screen {
+            TitleScreen::Main => {
+                let (item, step) = match choice {
+                    MenuEvent::Select(item) => (TitleItem::ALL[item], None),
+                    MenuEvent::Adjust(item, step) => (TitleItem::ALL[item], Some(step)),
+                    MenuEvent::Back => continue,
+                };
+                match (item, step) {
+                    (TitleItem::Start, None) => {
+                        let before = scores.clone();
+                        last_game = Some(play(args, config, scores, controls, renderer));
+                        main_menu.footer = None;
+                        if *scores != before {
+                            if let Err(err) = scores.save() {
+                                main_menu.footer = Some(format!("high scores not saved: {}", err));
+                            }
+                        }
                     }
-                    // rotate left
-                    Event::Key(KeyEvent {
-                        code: KeyCode::Char('j'),
-                        modifiers: KeyModifiers::NONE,
-                        kind: KeyEventKind::Press,
-                        ..
-                    }) => {
-                        game.handle_input(Input::Counterclockwise, &stdout);
+                    (TitleItem::HighScores, None) => screen = TitleScreen::HighScores(high_scores_menu(scores, args.options.mode)),
+                    (TitleItem::Settings, None) => screen = TitleScreen::Settings(Setting::menu(config), config.clone()),
+                    (TitleItem::Quit, None) => break,
+                    (item, step) => {
+                        item.adjust(&mut args.options, step.unwrap_or(1));
+                        main_menu.items = TitleItem::items(&args.options);
                     }
-                    // rotate right
-                    Event::Key(KeyEvent {
-                        code: KeyCode::Char('k'),
-                        modifiers: KeyModifiers::NONE,
-                        kind: KeyEventKind::Press,
-                        ..
-                    }) => {
-                        game.handle_input(Input::Clockwise, &stdout);
+                }
+            }
+            TitleScreen::HighScores(_) => {
+                if matches!(choice, MenuEvent::Select(_) | MenuEvent::Back) {
+                    screen = TitleScreen::Main;
+                }
+            }
+            TitleScreen::Settings(menu, before) => {
+                if let Some(saved) = settings_choice(config, before, controls, menu, choice) {
+                    main_menu.footer = saved.err().map(|err| format!("settings not saved: {}", err));
+                    screen = TitleScreen::Main;
+                }
+            }
+        }
+    }
+
+    last_game
+}
+
+// a choice in the settings menu. leaving the menu applies the settings and saves them if they
+// differ from before it was opened, the outcome of saving is returned once it is left
+fn settings_choice(
+    config: &mut Config,
+    before: &Config,
+    controls: &mut Controls,
+    menu: &mut Menu,
+    choice: MenuEvent,
+) -> Option<io::Result<()>> {
+    match choice {
+        MenuEvent::Select(item) | MenuEvent::Adjust(item, _) if Setting::ALL[item] != Setting::Back => {
+            let step = if let MenuEvent::Adjust(_, step) = choice { step } else { 1 };
+            Setting::ALL[item].adjust(config, step);
+            menu.items = Setting::items(config);
+            None
+        }
+        MenuEvent::Adjust(..) => None,
+        MenuEvent::Select(_) | MenuEvent::Back => {
+            // an unchanged config is not written, that would only drop the comments in the file
+            if config == before {
+                return Some(Ok(()));
+            }
+            controls.configure(config.keymap(), AutoShift::new(config.das, config.arr));
+            Some(config.save())
+        }
+    }
+}
+
+// a menu open over a paused game
+enum Overlay {
+    Pause(Menu),
+    Settings(Menu, Config), // with the config as it was when the menu opened
+}
+
+impl Overlay {
+    fn menu(&self) -> &Menu {
+        match self {
+            Overlay::Pause(menu) | Overlay::Settings(menu, _) => menu,
+        }
+    }
+
+    fn menu_mut(&mut self) -> &mut Menu {
+        match self {
+            Overlay::Pause(menu) | Overlay::Settings(menu, _) => menu,
+        }
+    }
+}
+
+const PAUSE_ITEMS: [&str; 4] = ["resume", "restart", "settings", "quit"];
+
+fn pause_menu() -> Menu {
+    Menu::new("paused", PAUSE_ITEMS.iter().map(|item| item.to_string()).collect())
+}
+
+// a fixed seed deals the same blocks again, otherwise roll a new one
+fn new_game(args: &Args) -> Game {
+    let seed = if args.seeded { args.options.seed } else { rand::random() };
+    Game::with_options(GameOptions { seed, ..args.options.clone() })
+}
+
+// live games driven by the keyboard until quit, returns the last game played.
+// every finished game goes on the high score list if it is good enough
+fn play(
+    args: &Args,
+    config: &mut Config,
+    scores: &mut HighScores,
+    controls: &mut Controls,
+    renderer: &mut TerminalRenderer,
+) -> Game {
+    let mut game = new_game(args);
+    let mut timer = SprintTimer::start();
+    controls.reset();
+    renderer.init(&game);
+    let mut clock = GameClock::new();
+    let mut inputs = Vec::new(); // pressed since the last tick
+    let mut overlay: Option<Overlay> = None; // the game is paused while a menu is open
+
+    loop {
+        // game logic advances in fixed ticks, independent of how often keys are pressed
+        let ticks = clock.ticks();
+        for _ in 0..ticks {
+            controls.tick(&game, TICK, &mut inputs);
+            let events = game.step(&inputs);
+            timer.record(&events);
+            if events.iter().any(|event| matches!(event, GameEvent::GameOver | GameEvent::Finished { .. })) {
+                scores.record(&game, timer.time());
+            }
+            inputs.clear();
+        }
+
+        if ticks > 0 {
+            match &overlay {
+                Some(overlay) => renderer.render_menu(&game, &timer, overlay.menu()),
+                None => renderer.render_timed(&game, &timer),
+            }
+        }
+
+        if !poll(clock.until_next_tick()).unwrap() {
+            continue;
+        }
+        let Ok(Event::Key(event)) = read() else {
+            continue;
+        };
+
+        let Some(current) = &mut overlay else {
+            match controls.key(&event, &mut inputs) {
+                Some(Action::Quit) => break,
+                // restart once the game is over
+                Some(Action::Restart) if game.is_game_over() => {
+                    game = new_game(args);
+                    timer = SprintTimer::start();
+                    inputs.clear();
+                    controls.reset();
+                    renderer.init(&game);
+                }
+                Some(Action::Pause) if !game.is_game_over() => {
+                    game.pause();
+                    timer.pause();
+                    inputs.clear();
+                    controls.reset();
+                    overlay = Some(Overlay::Pause(pause_menu()));
+                }
+                _ => (),
+            }
+            continue;
+        };
+
+        let Some(action) = controls.menu_key(&event) else {
+            continue;
+        };
+        if action == Action::Quit {
+            break;
+        }
+        let Some(choice) = current.menu_mut().navigate(action) else {
+            continue;
+        };
+
+        match current {
+            Overlay::Pause(_) => match choice {
+                MenuEvent::Back | MenuEvent::Select(0) => {
+                    game.resume();
+                    timer.resume();
+                    overlay = None;
+                }
+                MenuEvent::Select(1) => {
+                    game = new_game(args);
+                    timer = SprintTimer::start();
+                    renderer.init(&game);
+                    overlay = None;
+                }
+                MenuEvent::Select(2) => overlay = Some(Overlay::Settings(Setting::menu(config), config.clone())),
+                MenuEvent::Select(_) => break,
+                MenuEvent::Adjust(..) => (),
+            },
+            Overlay::Settings(menu, before) => {
+                if let Some(saved) = settings_choice(config, before, controls, menu, choice) {
+                    let mut menu = pause_menu();
+                    if let Err(err) = saved {
+                        menu.footer = Some(format!("settings not saved: {}", err));
                     }
+                    overlay = Some(Overlay::Pause(menu));
+                }
+            }
+        }
+    }
+
+    game
+}
+
+// plays a recorded game back tick by tick, with pause, fast forward and frame stepping
+fn play_replay(replay: &Replay, renderer: &mut TerminalRenderer) -> Game {
+    let mut game = Game::with_options(replay.options.clone());
+    renderer.init_replay(&game);
+    let mut clock = GameClock::new();
+    let mut next_input = 0;
+    let mut paused = false;
+    let mut fast_forward = false;
+
+    loop {
+        let ticks = clock.ticks();
+        let steps = match (paused, fast_forward) {
+            (true, _) => 0,
+            (false, true) => ticks * FAST_FORWARD,
+            (false, false) => ticks,
+        };
+        for _ in 0..steps {
+            replay_tick(&mut game, replay, &mut next_input);
+        }
+
+        if ticks > 0 {
+            let state = if paused { "paused" } else if fast_forward { "x4" } else { "" };
+            renderer.render_replay(&game, &format!("replay {:<6} tick {}", state, game.ticks()));
+        }
+
+        if poll(clock.until_next_tick()).unwrap() {
+            if let Ok(Event::Key(KeyEvent { code: KeyCode::Char(key), kind: KeyEventKind::Press, .. })) = read() {
+                match key {
+                    'q' => break,
+                    'p' => paused = !paused,
+                    'f' => fast_forward = !fast_forward,
+                    'n' if paused => replay_tick(&mut game, replay, &mut next_input),
                     _ => (),
                 }
             }
+        }
+    }
 
+    game
+}
+
+// apply the inputs recorded for the current tick and advance the game by one tick
+fn replay_tick(game: &mut Game, replay: &Replay, next_input: &mut usize) {
+    let mut inputs = Vec::new();
+    while let Some(&(tick, input)) = replay.inputs.get(*next_input) {
+        if tick > game.ticks() {
+            break;
         }
+        inputs.push(input);
+        *next_input += 1;
     }
 
-    execute!(stdout, LeaveAlternateScreen).unwrap();
-    disable_raw_mode().unwrap();
+    game.step(&inputs);
 }
\ No newline at end of file
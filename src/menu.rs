@@ -0,0 +1,209 @@
+use std::time::Duration;
+
+use retris::board::{MAX_HEIGHT, MAX_WIDTH, MIN_SIZE};
+use retris::clock::TICK;
+use retris::level::MAX_START_LEVEL;
+use retris::{GameMode, GameOptions, RandomizerKind};
+
+use crate::config::Config;
+use crate::keymap::{Action, Preset};
+use crate::scores::HighScores;
+use crate::terminal::format_time;
+
+const MAX_DAS: Duration = Duration::from_millis(500);
+const MAX_ARR: Duration = Duration::from_millis(200);
+
+// what a key press in a menu amounts to, moving the selection is handled by the menu itself
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MenuEvent {
+    Select(usize),
+    Adjust(usize, i32), // left or right on an item, -1 or 1
+    Back,
+}
+
+// a list of choices drawn over the game and moved through with the menu keys
+#[derive(Debug, Clone)]
+pub struct Menu {
+    pub title: &'static str,
+    pub text: Vec<String>, // shown between the title and the items
+    pub items: Vec<String>,
+    pub selected: usize,
+    pub footer: Option<String>, // shown under the items, e.g. why the settings could not be saved
+}
+
+impl Menu {
+    pub fn new(title: &'static str, items: Vec<String>) -> Menu {
+        Menu {
+            title,
+            text: Vec::new(),
+            items,
+            selected: 0,
+            footer: None,
+        }
+    }
+
+    pub fn navigate(&mut self, action: Action) -> Option<MenuEvent> {
+        let count = self.items.len();
+        match action {
+            Action::MenuUp => self.selected = (self.selected + count - 1) % count,
+            Action::MenuDown => self.selected = (self.selected + 1) % count,
+            Action::MenuLeft => return Some(MenuEvent::Adjust(self.selected, -1)),
+            Action::MenuRight => return Some(MenuEvent::Adjust(self.selected, 1)),
+            Action::Select => return Some(MenuEvent::Select(self.selected)),
+            Action::Back => return Some(MenuEvent::Back),
+            _ => (),
+        }
+
+        None
+    }
+}
+
+// the entries of the settings menu, in menu order
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Setting {
+    Keys,
+    Das,
+    Arr,
+    Back,
+}
+
+impl Setting {
+    pub const ALL: [Setting; 4] = [Setting::Keys, Setting::Das, Setting::Arr, Setting::Back];
+
+    pub fn menu(config: &Config) -> Menu {
+        Menu::new("settings", Setting::items(config))
+    }
+
+    pub fn items(config: &Config) -> Vec<String> {
+        Setting::ALL.iter().map(|setting| setting.label(config)).collect()
+    }
+
+    fn label(&self, config: &Config) -> String {
+        match self {
+            Setting::Keys => format!("keys: {}", config.preset.name()),
+            Setting::Das => format!("das: {} ms", config.das.as_millis()),
+            Setting::Arr => format!("arr: {} ms", config.arr.as_millis()),
+            Setting::Back => "back".to_string(),
+        }
+    }
+
+    // step the setting forwards or backwards, timings move by one tick
+    pub fn adjust(&self, config: &mut Config, step: i32) {
+        match self {
+            Setting::Keys => config.preset = cycle(&Preset::ALL, config.preset, step),
+            Setting::Das => config.das = step_duration(config.das, step, MAX_DAS),
+            Setting::Arr => config.arr = step_duration(config.arr, step, MAX_ARR),
+            Setting::Back => (),
+        }
+    }
+}
+
+fn step_duration(duration: Duration, step: i32, max: Duration) -> Duration {
+    if step < 0 {
+        duration.saturating_sub(TICK)
+    } else {
+        (duration + TICK).min(max)
+    }
+}
+
+// the entries of the title screen, in menu order
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TitleItem {
+    Start,
+    Mode,
+    Level,
+    Randomizer,
+    Width,
+    Height,
+    HighScores,
+    Settings,
+    Quit,
+}
+
+impl TitleItem {
+    pub const ALL: [TitleItem; 9] = [
+        TitleItem::Start,
+        TitleItem::Mode,
+        TitleItem::Level,
+        TitleItem::Randomizer,
+        TitleItem::Width,
+        TitleItem::Height,
+        TitleItem::HighScores,
+        TitleItem::Settings,
+        TitleItem::Quit,
+    ];
+
+    pub fn menu(options: &GameOptions) -> Menu {
+        Menu::new("retris", TitleItem::items(options))
+    }
+
+    pub fn items(options: &GameOptions) -> Vec<String> {
+        TitleItem::ALL.iter().map(|item| item.label(options)).collect()
+    }
+
+    fn label(&self, options: &GameOptions) -> String {
+        match self {
+            TitleItem::Start => "start".to_string(),
+            TitleItem::Mode => format!("mode: {}", options.mode.name()),
+            TitleItem::Level => format!("level: {}", options.start_level),
+            TitleItem::Randomizer => format!("randomizer: {}", options.randomizer.name()),
+            TitleItem::Width => format!("width: {}", options.board_width),
+            TitleItem::Height => format!("height: {}", options.board_height),
+            TitleItem::HighScores => "high scores".to_string(),
+            TitleItem::Settings => "settings".to_string(),
+            TitleItem::Quit => "quit".to_string(),
+        }
+    }
+
+    // step a game option forwards or backwards, lists wrap around and numbers stop at their limits
+    pub fn adjust(&self, options: &mut GameOptions, step: i32) {
+        match self {
+            TitleItem::Mode => options.mode = cycle(&GameMode::ALL, options.mode, step),
+            TitleItem::Level => options.start_level = step_number(options.start_level, step, 1, MAX_START_LEVEL),
+            TitleItem::Randomizer => options.randomizer = cycle(&RandomizerKind::ALL, options.randomizer, step),
+            TitleItem::Width => {
+                options.board_width = step_number(options.board_width as u32, step, MIN_SIZE as u32, MAX_WIDTH as u32) as u8;
+            }
+            TitleItem::Height => {
+                options.board_height = step_number(options.board_height as u32, step, MIN_SIZE as u32, MAX_HEIGHT as u32) as u8;
+            }
+            _ => (),
+        }
+    }
+}
+
+// the best results of one mode
+pub fn high_scores_menu(scores: &HighScores, mode: GameMode) -> Menu {
+    let mut menu = Menu::new("high scores", vec!["back".to_string()]);
+    let entries: Vec<String> = match mode {
+        GameMode::Endless => scores
+            .endless
+            .iter()
+            .enumerate()
+            .map(|(i, entry)| format!("{:>2}. {:>9}  lines {:>4}  level {:>2}", i + 1, entry.score, entry.lines, entry.level))
+            .collect(),
+        GameMode::Sprint(lines) => scores
+            .sprint_times(lines)
+            .enumerate()
+            .map(|(i, entry)| format!("{:>2}. {:>10}", i + 1, format_time(entry.time)))
+            .collect(),
+    };
+
+    menu.text.push(mode.name());
+    menu.text.push(String::new());
+    if entries.is_empty() {
+        menu.text.push("no finished games yet".to_string());
+    }
+    menu.text.extend(entries);
+
+    menu
+}
+
+fn cycle<T: Copy + PartialEq>(all: &[T], current: T, step: i32) -> T {
+    let index = all.iter().position(|&value| value == current).unwrap() as i32;
+    all[(index + step).rem_euclid(all.len() as i32) as usize]
+}
+
+fn step_number(value: u32, step: i32, min: u32, max: u32) -> u32 {
+    value.saturating_add_signed(step).clamp(min, max)
+}
\ No newline at end of file
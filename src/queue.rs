@@ -0,0 +1,44 @@
+use std::collections::VecDeque;
+
+use crate::block::{new_block, Block};
+use crate::randomizer::Randomizer;
+
+pub const PREVIEW_SIZE: usize = 6; // upcoming blocks that can be peeked at
+
+// upcoming blocks, drawn from the randomizer on demand so the preview can look past the end of a bag
+#[derive(Debug)]
+pub struct BlockQueue {
+    blocks: VecDeque<Block>,
+    randomizer: Box<dyn Randomizer>,
+}
+
+impl BlockQueue {
+    pub fn new(randomizer: Box<dyn Randomizer>) -> BlockQueue {
+        let mut queue = BlockQueue {
+            blocks: VecDeque::new(),
+            randomizer,
+        };
+        queue.refill();
+
+        queue
+    }
+
+    // keep at least one block more than the preview shows
+    fn refill(&mut self) {
+        while self.blocks.len() <= PREVIEW_SIZE {
+            self.blocks.push_back(new_block(self.randomizer.next_type()));
+        }
+    }
+
+    pub fn next_block(&mut self) -> Block {
+        let block = self.blocks.pop_front().expect("queue is refilled after every pop");
+        self.refill();
+
+        block
+    }
+
+    // the next n blocks in spawn order, n is capped at PREVIEW_SIZE
+    pub fn peek(&self, n: usize) -> impl Iterator<Item = &Block> {
+        self.blocks.iter().take(n.min(PREVIEW_SIZE))
+    }
+}
\ No newline at end of file
@@ -0,0 +1,209 @@
+use std::fmt::Debug;
+
+use rand::rngs::StdRng;
+use rand::seq::SliceRandom;
+use rand::{Rng, SeedableRng};
+
+use crate::block::BlockType;
+
+const ALL_TYPES: [BlockType; 7] = [
+    BlockType::I,
+    BlockType::J,
+    BlockType::L,
+    BlockType::O,
+    BlockType::S,
+    BlockType::Z,
+    BlockType::T,
+];
+
+// decides the order blocks are dealt in. the same seed always gives the same sequence
+pub trait Randomizer: Debug {
+    fn next_type(&mut self) -> BlockType;
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RandomizerKind {
+    SevenBag,
+    FourteenBag,
+    Random,
+    Tgm,
+    Nes,
+}
+
+impl RandomizerKind {
+    pub const ALL: [RandomizerKind; 5] = [
+        RandomizerKind::SevenBag,
+        RandomizerKind::FourteenBag,
+        RandomizerKind::Random,
+        RandomizerKind::Tgm,
+        RandomizerKind::Nes,
+    ];
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            RandomizerKind::SevenBag => "7bag",
+            RandomizerKind::FourteenBag => "14bag",
+            RandomizerKind::Random => "random",
+            RandomizerKind::Tgm => "tgm",
+            RandomizerKind::Nes => "nes",
+        }
+    }
+
+    pub fn from_name(name: &str) -> Option<RandomizerKind> {
+        RandomizerKind::ALL.into_iter().find(|kind| kind.name() == name)
+    }
+
+    pub fn create(&self, seed: u64) -> Box<dyn Randomizer> {
+        let rng = StdRng::seed_from_u64(seed);
+        match self {
+            RandomizerKind::SevenBag => Box::new(Bag::new(rng, 1)),
+            RandomizerKind::FourteenBag => Box::new(Bag::new(rng, 2)),
+            RandomizerKind::Random => Box::new(PureRandom { rng }),
+            RandomizerKind::Tgm => Box::new(Tgm::new(rng)),
+            RandomizerKind::Nes => Box::new(Nes { rng, last: None }),
+        }
+    }
+}
+
+// shuffled bags holding every block type `copies` times
+#[derive(Debug)]
+pub struct Bag {
+    rng: StdRng,
+    copies: usize,
+    bag: Vec<BlockType>,
+}
+
+impl Bag {
+    pub fn new(rng: StdRng, copies: usize) -> Bag {
+        Bag {
+            rng,
+            copies,
+            bag: Vec::new(),
+        }
+    }
+}
+
+impl Randomizer for Bag {
+    fn next_type(&mut self) -> BlockType {
+        if self.bag.is_empty() {
+            for _ in 0..self.copies {
+                self.bag.extend(ALL_TYPES);
+            }
+            self.bag.shuffle(&mut self.rng);
+        }
+
+        self.bag.pop().expect("bag was just refilled")
+    }
+}
+
+// every block type is equally likely every time
+#[derive(Debug)]
+pub struct PureRandom {
+    rng: StdRng,
+}
+
+impl Randomizer for PureRandom {
+    fn next_type(&mut self) -> BlockType {
+        *ALL_TYPES.choose(&mut self.rng).unwrap()
+    }
+}
+
+const TGM_HISTORY: usize = 4;
+const TGM_ROLLS: usize = 4;
+
+// TGM style: rerolls while the pick is in the last 4 blocks, giving up after 4 tries.
+// the history starts out full of S and Z and the first block is never S, Z or O
+#[derive(Debug)]
+pub struct Tgm {
+    rng: StdRng,
+    history: [BlockType; TGM_HISTORY],
+    first: bool,
+}
+
+impl Tgm {
+    pub fn new(rng: StdRng) -> Tgm {
+        Tgm {
+            rng,
+            history: [BlockType::Z, BlockType::S, BlockType::Z, BlockType::S],
+            first: true,
+        }
+    }
+}
+
+impl Randomizer for Tgm {
+    fn next_type(&mut self) -> BlockType {
+        let next = if self.first {
+            self.first = false;
+            *[BlockType::I, BlockType::J, BlockType::L, BlockType::T].choose(&mut self.rng).unwrap()
+        } else {
+            let mut pick = *ALL_TYPES.choose(&mut self.rng).unwrap();
+            for _ in 1..TGM_ROLLS {
+                if !self.history.contains(&pick) {
+                    break;
+                }
+                pick = *ALL_TYPES.choose(&mut self.rng).unwrap();
+            }
+            pick
+        };
+
+        self.history.rotate_right(1);
+        self.history[0] = next;
+
+        next
+    }
+}
+
+// NES style: one of 8 outcomes, rolling again once on the extra outcome or a repeat
+#[derive(Debug)]
+pub struct Nes {
+    rng: StdRng,
+    last: Option<BlockType>,
+}
+
+impl Randomizer for Nes {
+    fn next_type(&mut self) -> BlockType {
+        let roll = self.rng.gen_range(0..ALL_TYPES.len() + 1);
+        let next = match ALL_TYPES.get(roll) {
+            Some(&pick) if Some(pick) != self.last => pick,
+            _ => *ALL_TYPES.choose(&mut self.rng).unwrap(),
+        };
+
+        self.last = Some(next);
+        next
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn deal(kind: RandomizerKind, seed: u64, count: usize) -> Vec<BlockType> {
+        let mut randomizer = kind.create(seed);
+        (0..count).map(|_| randomizer.next_type()).collect()
+    }
+
+    #[test]
+    fn same_seed_deals_the_same_blocks() {
+        for kind in RandomizerKind::ALL {
+            assert_eq!(deal(kind, 42, 200), deal(kind, 42, 200), "{}", kind.name());
+            assert_ne!(deal(kind, 42, 200), deal(kind, 43, 200), "{}", kind.name());
+        }
+    }
+
+    #[test]
+    fn every_bag_holds_each_block_once() {
+        for bag in deal(RandomizerKind::SevenBag, 7, 70).chunks(7) {
+            let mut bag = bag.to_vec();
+            bag.sort_by_key(|&kind| kind as u8);
+            assert_eq!(bag, ALL_TYPES);
+        }
+    }
+
+    #[test]
+    fn tgm_never_starts_with_s_z_or_o() {
+        for seed in 0..100 {
+            let first = deal(RandomizerKind::Tgm, seed, 1)[0];
+            assert!(![BlockType::S, BlockType::Z, BlockType::O].contains(&first));
+        }
+    }
+}
\ No newline at end of file
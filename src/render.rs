@@ -0,0 +1,10 @@
+use crate::game::Game;
+
+// draws a game, the engine itself never touches the screen
+pub trait Renderer {
+    // draw everything that stays the same for the whole game, like the walls
+    fn init(&mut self, game: &Game);
+
+    // draw the current state of the game
+    fn render(&mut self, game: &Game);
+}
\ No newline at end of file
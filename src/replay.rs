@@ -0,0 +1,225 @@
+use std::fs;
+use std::io::{Error, ErrorKind, Result};
+use std::path::Path;
+use std::time::Duration;
+
+use crate::board::{MAX_HEIGHT, MAX_WIDTH, MIN_SIZE};
+use crate::game::{GameMode, GameOptions};
+use crate::input::Input;
+use crate::randomizer::RandomizerKind;
+
+const MAGIC: &[u8; 4] = b"RTRP";
+const VERSION: u8 = 1;
+const HEADER_SIZE: usize = 25; // up to the mode, which is as long as it needs to be
+
+const INPUTS: [Input; 7] = [
+    Input::Left,
+    Input::Right,
+    Input::Clockwise,
+    Input::Counterclockwise,
+    Input::Drop,
+    Input::SoftDrop,
+    Input::Hold,
+];
+
+// everything needed to play a game again: its options (including the seed)
+// and every input together with the logic tick it was applied on
+#[derive(Debug, Clone)]
+pub struct Replay {
+    pub options: GameOptions,
+    pub inputs: Vec<(u64, Input)>,
+}
+
+impl Replay {
+    pub fn save(&self, path: &Path) -> Result<()> {
+        fs::write(path, self.to_bytes()?)
+    }
+
+    pub fn load(path: &Path) -> Result<Replay> {
+        Replay::from_bytes(&fs::read(path)?)
+    }
+
+    // header followed by one (tick delta as LEB128, input byte) pair per input.
+    // the mode ends the header, 0 for endless and the target lines plus one for a sprint
+    pub fn to_bytes(&self) -> Result<Vec<u8>> {
+        // refused rather than written as some other level
+        let start_level = u8::try_from(self.options.start_level)
+            .map_err(|_| Error::new(ErrorKind::InvalidInput, "start level does not fit in a replay"))?;
+
+        let mut bytes = Vec::new();
+        bytes.extend_from_slice(MAGIC);
+        bytes.push(VERSION);
+        bytes.extend_from_slice(&self.options.seed.to_le_bytes());
+        bytes.push(RandomizerKind::ALL.iter().position(|&kind| kind == self.options.randomizer).unwrap() as u8);
+        bytes.push(start_level);
+        bytes.push(self.options.board_width);
+        bytes.push(self.options.board_height);
+        bytes.extend_from_slice(&(self.options.lock_delay.as_nanos() as u64).to_le_bytes());
+        match self.options.mode {
+            GameMode::Endless => write_varint(&mut bytes, 0),
+            GameMode::Sprint(lines) => write_varint(&mut bytes, lines as u64 + 1),
+        }
+
+        let mut last_tick = 0;
+        for &(tick, input) in &self.inputs {
+            write_varint(&mut bytes, tick - last_tick);
+            last_tick = tick;
+            bytes.push(INPUTS.iter().position(|&known| known == input).unwrap() as u8);
+        }
+
+        Ok(bytes)
+    }
+
+    pub fn from_bytes(bytes: &[u8]) -> Result<Replay> {
+        let invalid = || Error::new(ErrorKind::InvalidData, "not a retris replay");
+
+        if bytes.len() < HEADER_SIZE || &bytes[0..4] != MAGIC || bytes[4] != VERSION {
+            return Err(invalid());
+        }
+
+        let mut rest = bytes[HEADER_SIZE..].iter();
+        let mode = match read_varint(&mut rest).ok_or_else(invalid)? {
+            0 => GameMode::Endless,
+            lines => GameMode::Sprint(u32::try_from(lines - 1).map_err(|_| invalid())?),
+        };
+        // no game can be played on a board of any other size
+        if !(MIN_SIZE..=MAX_WIDTH).contains(&bytes[15]) || !(MIN_SIZE..=MAX_HEIGHT).contains(&bytes[16]) {
+            return Err(invalid());
+        }
+        let options = GameOptions {
+            mode,
+            seed: u64::from_le_bytes(bytes[5..13].try_into().unwrap()),
+            randomizer: *RandomizerKind::ALL.get(bytes[13] as usize).ok_or_else(invalid)?,
+            start_level: bytes[14] as u32,
+            board_width: bytes[15],
+            board_height: bytes[16],
+            lock_delay: Duration::from_nanos(u64::from_le_bytes(bytes[17..25].try_into().unwrap())),
+        };
+
+        let mut inputs = Vec::new();
+        let mut tick = 0u64;
+        while rest.len() > 0 {
+            tick = tick.checked_add(read_varint(&mut rest).ok_or_else(invalid)?).ok_or_else(invalid)?;
+            let input = *INPUTS.get(*rest.next().ok_or_else(invalid)? as usize).ok_or_else(invalid)?;
+            inputs.push((tick, input));
+        }
+
+        Ok(Replay { options, inputs })
+    }
+}
+
+// LEB128: seven bits at a time, lowest first, the top bit set on every byte but the last
+fn write_varint(bytes: &mut Vec<u8>, mut value: u64) {
+    loop {
+        let byte = (value & 0x7f) as u8;
+        value >>= 7;
+        if value == 0 {
+            bytes.push(byte);
+            return;
+        }
+        bytes.push(byte | 0x80);
+    }
+}
+
+// None if the bytes end in the middle of the number or it does not fit in 64 bits
+fn read_varint(bytes: &mut std::slice::Iter<u8>) -> Option<u64> {
+    let mut value = 0u64;
+    let mut shift = 0;
+    loop {
+        let byte = *bytes.next()?;
+        value |= ((byte & 0x7f) as u64) << shift;
+        if byte & 0x80 == 0 {
+            return Some(value);
+        }
+        shift += 7;
+        if shift >= 64 {
+            return None;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::game::Game;
+    use crate::level::MAX_START_LEVEL;
+
+    // a game played with a few inputs every now and then
+    fn play(options: GameOptions) -> Game {
+        let mut game = Game::with_options(options);
+        let inputs = [Input::Left, Input::Clockwise, Input::SoftDrop, Input::Hold, Input::Right, Input::Drop];
+        for tick in 0..3000 {
+            let input = [inputs[tick / 13 % inputs.len()]];
+            game.step(if tick % 13 == 0 { &input } else { &[] });
+        }
+
+        game
+    }
+
+    // the same way the frontend plays replays back. a game that ended stops counting
+    // ticks, the inputs of its last tick still have to be applied
+    fn play_back(replay: &Replay, ticks: u64) -> Game {
+        let mut game = Game::with_options(replay.options.clone());
+        let mut next = replay.inputs.iter().peekable();
+        while game.ticks() < ticks || next.peek().is_some() {
+            let mut inputs = Vec::new();
+            while let Some(&(_, input)) = next.next_if(|&&(tick, _)| tick <= game.ticks()) {
+                inputs.push(input);
+            }
+            game.step(&inputs);
+        }
+
+        game
+    }
+
+    #[test]
+    fn replays_play_back_the_same_game() {
+        let options = GameOptions {
+            mode: GameMode::Sprint(30),
+            start_level: 5,
+            board_width: 8,
+            board_height: 16,
+            randomizer: RandomizerKind::Tgm,
+            seed: 99,
+            lock_delay: Duration::from_millis(300),
+        };
+        let game = play(options);
+        let replay = Replay::from_bytes(&game.replay().to_bytes().unwrap()).unwrap();
+
+        assert_eq!(replay.options.mode, GameMode::Sprint(30));
+        assert_eq!(replay.options.lock_delay, Duration::from_millis(300));
+        assert_eq!(replay.inputs, game.replay().inputs);
+
+        let again = play_back(&replay, game.ticks());
+        assert_eq!(again.board(), game.board());
+        assert_eq!(again.scoring().score(), game.scoring().score());
+        assert_eq!(again.lines(), game.lines());
+        assert_eq!(again.is_game_over(), game.is_game_over());
+    }
+
+    #[test]
+    fn replays_keep_the_options_as_played() {
+        let game = Game::with_options(GameOptions { start_level: 300, board_width: 99, seed: 1, ..GameOptions::default() });
+        let replay = Replay::from_bytes(&game.replay().to_bytes().unwrap()).unwrap();
+        assert_eq!(replay.options.start_level, MAX_START_LEVEL);
+        assert_eq!(replay.options.board_width, MAX_WIDTH);
+
+        let mut unplayable = game.replay();
+        unplayable.options.start_level = 300;
+        assert!(unplayable.to_bytes().is_err());
+    }
+
+    #[test]
+    fn broken_replays_are_rejected() {
+        let bytes = Game::new().replay().to_bytes().unwrap();
+        assert!(Replay::from_bytes(&bytes[..20]).is_err());
+
+        let mut wide = bytes.clone();
+        wide[15] = 254;
+        assert!(Replay::from_bytes(&wide).is_err());
+
+        let mut unknown = bytes.clone();
+        unknown.extend_from_slice(&[0, 200]);
+        assert!(Replay::from_bytes(&unknown).is_err());
+    }
+}
\ No newline at end of file
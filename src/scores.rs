@@ -0,0 +1,174 @@
+use std::io::Result;
+use std::path::PathBuf;
+use std::time::Duration;
+
+use retris::{Game, GameMode};
+
+use crate::storage::{self, invalid};
+
+pub const MAX_SCORES: usize = 10; // entries kept per mode
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Score {
+    pub score: u64,
+    pub lines: u32,
+    pub level: u32,
+}
+
+impl Score {
+    pub fn of(game: &Game) -> Score {
+        Score {
+            score: game.scoring().score(),
+            lines: game.lines(),
+            level: game.level(),
+        }
+    }
+}
+
+// a finished sprint
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SprintTime {
+    pub lines: u32, // the sprint's target
+    pub time: Duration,
+}
+
+// the best finished games, kept in scores.toml in the user's data directory
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct HighScores {
+    pub endless: Vec<Score>, // best first
+    pub sprint: Vec<SprintTime>, // fastest first, MAX_SCORES for every sprint length
+}
+
+impl HighScores {
+    // ~/.local/share/retris/scores.toml on linux, the platform equivalent elsewhere
+    pub fn path() -> Option<PathBuf> {
+        dirs::data_dir().map(|dir| dir.join("retris").join("scores.toml"))
+    }
+
+    // no file yet means no scores yet
+    pub fn load() -> Result<HighScores> {
+        match storage::load(HighScores::path())? {
+            Some(table) => HighScores::from_table(&table),
+            None => Ok(HighScores::default()),
+        }
+    }
+
+    pub fn save(&self) -> Result<()> {
+        storage::save(HighScores::path(), &self.to_table())
+    }
+
+    // add the result of a finished game, timed by the frontend. topping out only counts in endless.
+    // returns the rank it got, None if it did not make the list
+    pub fn record(&mut self, game: &Game, time: Duration) -> Option<usize> {
+        match game.mode() {
+            GameMode::Endless if game.is_game_over() => self.add(Score::of(game)),
+            GameMode::Sprint(lines) if game.is_finished() => self.add_sprint(SprintTime { lines, time }),
+            _ => None,
+        }
+    }
+
+    pub fn sprint_times(&self, lines: u32) -> impl Iterator<Item = &SprintTime> {
+        self.sprint.iter().filter(move |entry| entry.lines == lines)
+    }
+
+    fn add(&mut self, score: Score) -> Option<usize> {
+        let rank = self.endless.iter().position(|entry| score.score > entry.score).unwrap_or(self.endless.len());
+        if rank >= MAX_SCORES {
+            return None;
+        }
+
+        self.endless.insert(rank, score);
+        self.endless.truncate(MAX_SCORES);
+
+        Some(rank)
+    }
+
+    fn add_sprint(&mut self, sprint: SprintTime) -> Option<usize> {
+        let rank = self.sprint_times(sprint.lines).filter(|entry| entry.time <= sprint.time).count();
+        if rank >= MAX_SCORES {
+            return None;
+        }
+
+        let index = self.sprint.iter().position(|entry| entry.time > sprint.time).unwrap_or(self.sprint.len());
+        self.sprint.insert(index, sprint);
+        // drop whatever got pushed off this length's list
+        if let Some(slowest) = self
+            .sprint
+            .iter()
+            .enumerate()
+            .filter(|(_, entry)| entry.lines == sprint.lines)
+            .nth(MAX_SCORES)
+            .map(|(index, _)| index)
+        {
+            self.sprint.remove(slowest);
+        }
+
+        Some(rank)
+    }
+
+    fn from_table(table: &toml::Table) -> Result<HighScores> {
+        let mut scores = HighScores::default();
+
+        if let Some(endless) = table.get("endless") {
+            let entries = endless.as_array().ok_or_else(|| invalid("endless must be a list".to_string()))?;
+            for entry in entries {
+                scores.endless.push(Score {
+                    score: field(entry, "endless score", "score")?,
+                    lines: field(entry, "endless score", "lines")? as u32,
+                    level: field(entry, "endless score", "level")? as u32,
+                });
+            }
+        }
+
+        if let Some(sprint) = table.get("sprint") {
+            let entries = sprint.as_array().ok_or_else(|| invalid("sprint must be a list".to_string()))?;
+            for entry in entries {
+                scores.sprint.push(SprintTime {
+                    lines: field(entry, "sprint time", "lines")? as u32,
+                    time: Duration::from_millis(field(entry, "sprint time", "time_ms")?),
+                });
+            }
+        }
+
+        Ok(scores)
+    }
+
+    fn to_table(&self) -> toml::Table {
+        let endless = self
+            .endless
+            .iter()
+            .map(|score| {
+                let mut entry = toml::Table::new();
+                entry.insert("score".to_string(), (score.score as i64).into());
+                entry.insert("lines".to_string(), (score.lines as i64).into());
+                entry.insert("level".to_string(), (score.level as i64).into());
+                toml::Value::Table(entry)
+            })
+            .collect::<Vec<_>>();
+
+        let sprint = self
+            .sprint
+            .iter()
+            .map(|sprint| {
+                let mut entry = toml::Table::new();
+                entry.insert("lines".to_string(), (sprint.lines as i64).into());
+                entry.insert("time_ms".to_string(), ((sprint.time.as_secs_f64() * 1000.0).round() as i64).into());
+                toml::Value::Table(entry)
+            })
+            .collect::<Vec<_>>();
+
+        let mut table = toml::Table::new();
+        table.insert("endless".to_string(), endless.into());
+        table.insert("sprint".to_string(), sprint.into());
+
+        table
+    }
+}
+
+// a number of an entry in one of the lists
+fn field(entry: &toml::Value, kind: &str, name: &str) -> Result<u64> {
+    entry
+        .get(name)
+        .and_then(storage::unsigned)
+        .ok_or_else(|| invalid(format!("{} without {}", kind, name)))
+}
\ No newline at end of file
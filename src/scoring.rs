@@ -0,0 +1,157 @@
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TSpin {
+    None,
+    Mini,
+    Full,
+}
+
+// points for clearing 0, 1, 2, 3 or 4 rows, multiplied by the level
+const LINE_SCORES: [u64; 5] = [0, 100, 300, 500, 800];
+const T_SPIN_MINI_SCORES: [u64; 3] = [100, 200, 400];
+const T_SPIN_SCORES: [u64; 4] = [400, 800, 1200, 1600];
+const PERFECT_CLEAR_SCORES: [u64; 5] = [0, 800, 1200, 1800, 2000];
+const B2B_PERFECT_CLEAR_SCORE: u64 = 3200; // back-to-back tetris perfect clear
+const COMBO_SCORE: u64 = 50; // per combo step
+
+pub const SOFT_DROP_SCORE: u64 = 1; // per row
+pub const HARD_DROP_SCORE: u64 = 2; // per row
+
+// guideline scoring, fed with every lock and drop of the game
+#[derive(Debug, Default)]
+pub struct Scoring {
+    score: u64,
+    combo: Option<u32>, // consecutive locks that cleared rows, None until the first clear
+    back_to_back: bool, // the last clear was a tetris or a t-spin
+}
+
+impl Scoring {
+    pub fn new() -> Scoring {
+        Scoring::default()
+    }
+
+    pub fn score(&self) -> u64 {
+        self.score
+    }
+
+    // consecutive locks that cleared rows, None until the first clear
+    pub fn combo(&self) -> Option<u32> {
+        self.combo
+    }
+
+    // the last clear was a tetris or a t-spin
+    pub fn back_to_back(&self) -> bool {
+        self.back_to_back
+    }
+
+    // score a locked block, returns the points it earned
+    pub fn lock(&mut self, lines: usize, t_spin: TSpin, perfect_clear: bool, level: u32) -> u64 {
+        let level = level as u64;
+        let lines = lines.min(4);
+
+        let mut points = match t_spin {
+            TSpin::None => LINE_SCORES[lines],
+            TSpin::Mini => T_SPIN_MINI_SCORES[lines.min(2)],
+            TSpin::Full => T_SPIN_SCORES[lines.min(3)],
+        } * level;
+
+        if lines == 0 {
+            // locking without a clear ends the combo but keeps back-to-back alive
+            self.combo = None;
+            self.score += points;
+            return points;
+        }
+
+        let difficult = lines == 4 || t_spin != TSpin::None;
+        let back_to_back = difficult && self.back_to_back;
+        if back_to_back {
+            points += points / 2;
+        }
+        self.back_to_back = difficult;
+
+        let combo = self.combo.map_or(0, |combo| combo + 1);
+        self.combo = Some(combo);
+        points += COMBO_SCORE * combo as u64 * level;
+
+        if perfect_clear {
+            points += if back_to_back && lines == 4 {
+                B2B_PERFECT_CLEAR_SCORE
+            } else {
+                PERFECT_CLEAR_SCORES[lines]
+            } * level;
+        }
+
+        self.score += points;
+        points
+    }
+
+    pub fn soft_drop(&mut self, rows: u32) {
+        self.score += rows as u64 * SOFT_DROP_SCORE;
+    }
+
+    pub fn hard_drop(&mut self, rows: u32) {
+        self.score += rows as u64 * HARD_DROP_SCORE;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn line_clears_scale_with_the_level() {
+        let mut scoring = Scoring::new();
+        assert_eq!(scoring.lock(1, TSpin::None, false, 1), 100);
+        scoring.lock(0, TSpin::None, false, 1);
+        assert_eq!(scoring.lock(4, TSpin::None, false, 3), 2400);
+        assert_eq!(scoring.score, 2500);
+    }
+
+    #[test]
+    fn back_to_back_adds_half() {
+        let mut scoring = Scoring::new();
+        scoring.lock(4, TSpin::None, false, 1);
+        // a lock without a clear keeps back-to-back
+        scoring.lock(0, TSpin::None, false, 1);
+        assert_eq!(scoring.lock(1, TSpin::Full, false, 1), 1200);
+        assert!(scoring.back_to_back);
+
+        // a plain clear breaks it
+        scoring.lock(0, TSpin::None, false, 1);
+        scoring.lock(2, TSpin::None, false, 1);
+        assert!(!scoring.back_to_back);
+        scoring.lock(0, TSpin::None, false, 1);
+        assert_eq!(scoring.lock(4, TSpin::None, false, 1), 800);
+    }
+
+    #[test]
+    fn combo_grows_with_every_clearing_lock() {
+        let mut scoring = Scoring::new();
+        assert_eq!(scoring.lock(1, TSpin::None, false, 1), 100);
+        assert_eq!(scoring.lock(1, TSpin::None, false, 1), 150);
+        assert_eq!(scoring.lock(1, TSpin::None, false, 2), 400);
+        assert_eq!(scoring.combo, Some(2));
+
+        scoring.lock(0, TSpin::None, false, 1);
+        assert_eq!(scoring.combo, None);
+        assert_eq!(scoring.lock(1, TSpin::None, false, 1), 100);
+    }
+
+    #[test]
+    fn perfect_clears_add_a_bonus() {
+        let mut scoring = Scoring::new();
+        assert_eq!(scoring.lock(1, TSpin::None, true, 1), 900);
+
+        let mut scoring = Scoring::new();
+        scoring.lock(4, TSpin::None, false, 1);
+        // back-to-back tetris 1200, combo 50 and the back-to-back perfect clear bonus
+        assert_eq!(scoring.lock(4, TSpin::None, true, 1), 1200 + 50 + 3200);
+    }
+
+    #[test]
+    fn t_spins_without_lines_still_score() {
+        let mut scoring = Scoring::new();
+        assert_eq!(scoring.lock(0, TSpin::Mini, false, 1), 100);
+        assert_eq!(scoring.lock(0, TSpin::Full, false, 2), 800);
+        assert!(!scoring.back_to_back);
+    }
+}
\ No newline at end of file
@@ -0,0 +1,100 @@
+use std::time::Duration;
+
+use crate::game::Game;
+use crate::input::Input;
+use crate::level::gravity;
+
+pub const SOFT_DROP_FACTOR: u32 = 20; // a held soft drop falls this many times faster than gravity
+
+// a held soft drop key speeds gravity up for as long as it is down. the press itself
+// drops one row, that move is up to the caller
+#[derive(Debug, Clone, Default)]
+pub struct SoftDrop {
+    held: bool,
+    timer: Duration, // time since the last row dropped
+}
+
+impl SoftDrop {
+    pub fn new() -> SoftDrop {
+        SoftDrop::default()
+    }
+
+    pub fn press(&mut self) {
+        self.held = true;
+        self.timer = Duration::ZERO;
+    }
+
+    pub fn release(&mut self) {
+        self.held = false;
+    }
+
+    pub fn is_held(&self) -> bool {
+        self.held
+    }
+
+    // the soft drops due within the next dt, never more than the block has room to fall
+    pub fn tick(&mut self, game: &Game, dt: Duration, inputs: &mut Vec<Input>) {
+        if !self.held {
+            return;
+        }
+
+        // under 20G the block is on the stack already
+        let interval = gravity(game.level()) / SOFT_DROP_FACTOR;
+        if interval.is_zero() {
+            return;
+        }
+
+        self.timer += dt;
+        let due = (self.timer.as_nanos() / interval.as_nanos()) as usize;
+        self.timer -= interval * due as u32;
+
+        let block = game.current_block();
+        let room = (block.drop_y(game.board()) - block.y()) as usize;
+        inputs.extend(std::iter::repeat_n(Input::SoftDrop, due.min(room)));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::clock::TICK;
+
+    // rows soft dropped while playing the given number of ticks
+    fn held_for(soft_drop: &mut SoftDrop, game: &mut Game, ticks: u32) -> usize {
+        let mut dropped = 0;
+        for _ in 0..ticks {
+            let mut inputs = Vec::new();
+            soft_drop.tick(game, TICK, &mut inputs);
+            dropped += inputs.len();
+            game.step(&inputs);
+        }
+
+        dropped
+    }
+
+    #[test]
+    fn falls_faster_while_held() {
+        let mut game = Game::new();
+        let mut soft_drop = SoftDrop::new();
+        assert_eq!(held_for(&mut soft_drop, &mut game, 30), 0);
+
+        // just over half a second at level 1, where gravity takes a second per row
+        soft_drop.press();
+        assert_eq!(held_for(&mut soft_drop, &mut game, 31), SOFT_DROP_FACTOR as usize / 2);
+
+        soft_drop.release();
+        assert_eq!(held_for(&mut soft_drop, &mut game, 30), 0);
+    }
+
+    #[test]
+    fn never_drops_past_the_stack() {
+        let game = Game::new();
+        let mut soft_drop = SoftDrop::new();
+        soft_drop.press();
+
+        let mut inputs = Vec::new();
+        soft_drop.tick(&game, Duration::from_secs(10), &mut inputs);
+        let block = game.current_block();
+        assert_eq!(inputs.len(), (block.drop_y(game.board()) - block.y()) as usize);
+    }
+}
\ No newline at end of file
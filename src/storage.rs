@@ -0,0 +1,41 @@
+use std::fs;
+use std::io::{Error, ErrorKind, Result};
+use std::path::PathBuf;
+
+// the toml files kept in the user's directories, like the config and the high scores
+
+// the table in the file, None if there is no such file yet
+pub fn load(path: Option<PathBuf>) -> Result<Option<toml::Table>> {
+    let Some(path) = path else {
+        return Ok(None);
+    };
+
+    match fs::read_to_string(&path) {
+        Ok(text) => parse(&text).map(Some),
+        Err(err) if err.kind() == ErrorKind::NotFound => Ok(None),
+        Err(err) => Err(err),
+    }
+}
+
+// comments and formatting of an existing file are not kept
+pub fn save(path: Option<PathBuf>, table: &toml::Table) -> Result<()> {
+    let path = path.ok_or_else(|| Error::new(ErrorKind::NotFound, "no directory to save to"))?;
+    if let Some(dir) = path.parent() {
+        fs::create_dir_all(dir)?;
+    }
+
+    fs::write(path, table.to_string())
+}
+
+fn parse(text: &str) -> Result<toml::Table> {
+    text.parse().map_err(|err: toml::de::Error| invalid(err.message().to_string()))
+}
+
+// a whole number that cannot be negative
+pub fn unsigned(value: &toml::Value) -> Option<u64> {
+    value.as_integer().and_then(|value| u64::try_from(value).ok())
+}
+
+pub fn invalid(message: String) -> Error {
+    Error::new(ErrorKind::InvalidData, message)
+}
\ No newline at end of file
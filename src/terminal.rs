@@ -0,0 +1,383 @@
+use crate::colors::{get_color, get_ghost_color};
+use crate::menu::Menu;
+use crate::timer::SprintTimer;
+
+use retris::game::SPLIT_LINES;
+use retris::queue::PREVIEW_SIZE;
+use retris::{Block, Game, Renderer};
+
+use crossterm::cursor::MoveTo;
+use crossterm::style::{Color, Print, SetForegroundColor, ResetColor};
+use crossterm::terminal::{Clear, ClearType};
+use crossterm::{execute, queue};
+use std::io::{Stdout, Write};
+use std::mem::swap;
+use std::time::Duration;
+
+const MARGIN: u8 = 2; // side margin for cursor calculations
+const PREVIEW_COLUMN: u16 = 20; // distance between the hud and the next queue
+const PREVIEW_WIDTH: u16 = 8;
+const PREVIEW_ROWS: u16 = 3;
+const SEED_ROW: u16 = 12;
+const TIMER_ROW: u16 = 14; // sprint time and pace, the splits follow below
+// screen size for the title and high score menus
+const TITLE_WIDTH: u16 = 48;
+const TITLE_HEIGHT: u16 = 24;
+const REPLAY_HELP: &str = "p: pause  f: fast forward  n: next tick  q: quit";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct Cell {
+    ch: char,
+    color: Option<Color>,
+}
+
+const BLANK: Cell = Cell { ch: ' ', color: None };
+// never drawn, marks cells whose content on the terminal is unknown
+const UNKNOWN: Cell = Cell { ch: '\0', color: None };
+
+// minutes, seconds and milliseconds: 1:02.345
+pub fn format_time(time: Duration) -> String {
+    let millis = time.as_millis();
+    format!("{}:{:02}.{:03}", millis / 60_000, millis / 1000 % 60, millis % 1000)
+}
+
+// one character per terminal cell
+#[derive(Debug)]
+struct ScreenBuffer {
+    width: u16,
+    height: u16,
+    cells: Vec<Cell>,
+}
+
+impl ScreenBuffer {
+    fn new(width: u16, height: u16, fill: Cell) -> ScreenBuffer {
+        ScreenBuffer {
+            width,
+            height,
+            cells: vec![fill; width as usize * height as usize],
+        }
+    }
+
+    fn fill(&mut self, cell: Cell) {
+        self.cells.fill(cell);
+    }
+
+    // text running past the right edge is cut off
+    fn print(&mut self, x: u16, y: u16, text: &str, color: Option<Color>) {
+        if y >= self.height {
+            return;
+        }
+
+        for (i, ch) in text.chars().enumerate() {
+            let x = x as usize + i;
+            if x >= self.width as usize {
+                break;
+            }
+            self.cells[y as usize * self.width as usize + x] = Cell { ch, color };
+        }
+    }
+}
+
+// draws the game with crossterm, every board cell is two characters wide.
+// frames are drawn off screen and only the cells that changed are written out
+pub struct TerminalRenderer {
+    stdout: Stdout,
+    front: ScreenBuffer, // what the terminal currently shows
+    back: ScreenBuffer, // the frame being drawn
+}
+
+impl TerminalRenderer {
+    pub fn new(stdout: Stdout) -> TerminalRenderer {
+        TerminalRenderer {
+            stdout,
+            front: ScreenBuffer::new(0, 0, UNKNOWN),
+            back: ScreenBuffer::new(0, 0, BLANK),
+        }
+    }
+
+    // terminal position of a board cell, None for cells in the hidden buffer
+    fn screen_position(game: &Game, x: i16, y: i16) -> Option<(u16, u16)> {
+        let board = game.board();
+        if board.is_hidden_row(y) {
+            return None;
+        }
+
+        Some((x as u16 * 2 + MARGIN as u16, (y - board.buffer() as i16) as u16))
+    }
+
+    // left edge of the score and preview column next to the well
+    fn hud_x(game: &Game) -> u16 {
+        (game.board().width() + MARGIN) as u16 * 2 + 2
+    }
+
+    // draw a block in its spawn orientation at the given terminal position.
+    // no spawn orientation uses the bottom row of the 4x4 shape, so only 3 rows are drawn
+    fn draw_preview(&mut self, block: Option<&Block>, left: u16, top: u16) {
+        let Some(block) = block else {
+            return;
+        };
+
+        for y in 0..PREVIEW_ROWS as usize {
+            for x in 0..4 {
+                let value = block.spawn_shape()[y][x];
+                if value != 0 {
+                    self.back.print(left + x as u16 * 2, top + y as u16, "██", Some(get_color(value)));
+                }
+            }
+        }
+    }
+
+    fn draw_walls(&mut self, game: &Game) {
+        let board = game.board();
+        for y in 0..=board.height() as u16 {
+            if y < board.height() as u16 {
+                self.back.print(0, y, "<>", None);
+                self.back.print(board.width() as u16 * 2 + MARGIN as u16, y, "<>", None);
+            } else {
+                for x in 0..(board.width() + MARGIN) as u16 {
+                    self.back.print(x * 2, y, "<>", None);
+                }
+            }
+        }
+    }
+
+    fn draw_game_over(&mut self, game: &Game, timer: &SprintTimer) {
+        let center_y = game.board().height() as u16 / 2;
+        if game.is_finished() {
+            self.back.print(MARGIN as u16 + 6, center_y - 1, "FINISHED", None);
+            self.back.print(MARGIN as u16 + 6, center_y, &format_time(timer.time()), None);
+        } else {
+            self.back.print(MARGIN as u16 + 5, center_y - 1, "GAME  OVER", None);
+        }
+        self.back.print(MARGIN as u16 + 2, center_y + 1, "r: restart  q: quit", None);
+        // the seed replays the same block order with --seed
+        self.back.print(Self::hud_x(game), SEED_ROW, &format!("seed: {}", game.seed()), None);
+    }
+
+    // the menu's lines as drawn inside its box, the selected item is marked
+    fn menu_lines(menu: &Menu) -> Vec<String> {
+        let mut lines = vec![menu.title.to_string(), String::new()];
+        if !menu.text.is_empty() {
+            lines.extend(menu.text.iter().cloned());
+            lines.push(String::new());
+        }
+        for (i, item) in menu.items.iter().enumerate() {
+            let marker = if i == menu.selected { "> " } else { "  " };
+            lines.push(format!("{}{}", marker, item));
+        }
+        if let Some(footer) = &menu.footer {
+            lines.push(String::new());
+            lines.push(footer.clone());
+        }
+
+        lines
+    }
+
+    // a framed box with the lines in it
+    fn draw_box(&mut self, lines: &[String], left: u16, top: u16) {
+        let inner = lines.iter().map(|line| line.chars().count()).max().unwrap_or(0) as u16 + 2;
+        let height = lines.len() as u16 + 2;
+
+        let horizontal = "─".repeat(inner as usize);
+        self.back.print(left, top, &format!("┌{}┐", horizontal), None);
+        for (i, line) in lines.iter().enumerate() {
+            self.back.print(left, top + 1 + i as u16, &format!("│ {:<width$} │", line, width = inner as usize - 2), None);
+        }
+        self.back.print(left, top + height - 1, &format!("└{}┘", horizontal), None);
+    }
+
+    // walls, stack, blocks and hud. a paused game only shows the score, the stack and
+    // upcoming blocks stay hidden so the pause cannot be used to plan ahead
+    fn draw_game(&mut self, game: &Game, timer: &SprintTimer) {
+        self.back.fill(BLANK);
+        self.draw_walls(game);
+
+        let hidden = game.is_paused();
+        let board = game.board();
+        if !hidden {
+            for y in board.buffer() as usize..board.rows() as usize {
+                for (x, &value) in board.row(y).iter().enumerate() {
+                    if value != 0 {
+                        let (screen_x, screen_y) = Self::screen_position(game, x as i16, y as i16).unwrap();
+                        self.back.print(screen_x, screen_y, "██", Some(get_color(value)));
+                    }
+                }
+            }
+
+            // the ghost where the active block would land
+            for (x, y, value) in game.ghost_block().cells() {
+                if board.is_occupied(x, y) {
+                    continue;
+                }
+                if let Some((screen_x, screen_y)) = Self::screen_position(game, x, y) {
+                    self.back.print(screen_x, screen_y, "░░", Some(get_ghost_color(value)));
+                }
+            }
+
+            // current active block
+            for (x, y, value) in game.current_block().cells() {
+                if let Some((screen_x, screen_y)) = Self::screen_position(game, x, y) {
+                    self.back.print(screen_x, screen_y, "██", Some(get_color(value)));
+                }
+            }
+        }
+
+        // score and cleared lines next to the well
+        let hud_x = Self::hud_x(game);
+        let scoring = game.scoring();
+        self.back.print(hud_x, 1, &format!("score: {}", scoring.score()), None);
+        match game.mode().target_lines() {
+            Some(target) => self.back.print(hud_x, 2, &format!("lines: {}/{}", game.lines(), target), None),
+            None => self.back.print(hud_x, 2, &format!("lines: {}", game.lines()), None),
+        }
+        self.back.print(hud_x, 3, &format!("level: {}", game.level()), None);
+        self.back.print(hud_x, 4, &format!("combo: {}", scoring.combo().unwrap_or(0)), None);
+        if scoring.back_to_back() {
+            self.back.print(hud_x, 5, "b2b", None);
+        }
+
+        self.back.print(hud_x, 7, "hold:", None);
+        // upcoming blocks in a second column
+        let next_x = hud_x + PREVIEW_COLUMN;
+        self.back.print(next_x, 1, "next:", None);
+        if !hidden {
+            self.draw_preview(game.hold(), hud_x, 8);
+            for (i, block) in game.next_blocks(PREVIEW_SIZE).enumerate() {
+                self.draw_preview(Some(block), next_x, 2 + i as u16 * PREVIEW_ROWS);
+            }
+        }
+
+        if let Some(target) = game.mode().target_lines() {
+            self.draw_sprint(game, timer, target);
+        }
+
+        if game.is_game_over() {
+            self.draw_game_over(game, timer);
+        }
+    }
+
+    // the running time, the finishing time it is on pace for and the splits so far
+    fn draw_sprint(&mut self, game: &Game, timer: &SprintTimer, target: u32) {
+        let hud_x = Self::hud_x(game);
+        self.back.print(hud_x, TIMER_ROW, &format!("time: {}", format_time(timer.time())), None);
+        if game.lines() > 0 && !game.is_finished() {
+            let pace = timer.time().mul_f64(target as f64 / game.lines() as f64);
+            self.back.print(hud_x, TIMER_ROW + 1, &format!("pace: {}", format_time(pace)), None);
+        }
+
+        for (i, split) in timer.splits().iter().enumerate() {
+            let lines = (i as u32 + 1) * SPLIT_LINES;
+            self.back.print(hud_x, TIMER_ROW + 3 + i as u16, &format!("{:>3}: {}", lines, format_time(*split)), None);
+        }
+    }
+
+    // a live game, timed by the wall clock
+    pub fn render_timed(&mut self, game: &Game, timer: &SprintTimer) {
+        self.draw_game(game, timer);
+        self.present();
+    }
+
+    // the game with a menu on top, centered over the well
+    pub fn render_menu(&mut self, game: &Game, timer: &SprintTimer, menu: &Menu) {
+        self.draw_game(game, timer);
+        let lines = Self::menu_lines(menu);
+        let top = (game.board().height() as u16).saturating_sub(lines.len() as u16 + 2) / 2;
+        self.draw_box(&lines, MARGIN as u16, top);
+        self.present();
+    }
+
+    // a menu on its own, under a strip in the block colors
+    pub fn render_title(&mut self, menu: &Menu) {
+        if (self.back.width, self.back.height) != (TITLE_WIDTH, TITLE_HEIGHT) {
+            self.resize(TITLE_WIDTH, TITLE_HEIGHT);
+        }
+
+        self.back.fill(BLANK);
+        for color in 1..=7 {
+            self.back.print(MARGIN as u16 + (color as u16 - 1) * 2, 1, "██", Some(get_color(color)));
+        }
+        self.draw_box(&Self::menu_lines(menu), MARGIN as u16, 3);
+        self.present();
+    }
+
+    // a replayed game with its playback status and controls under everything else
+    pub fn render_replay(&mut self, game: &Game, status: &str) {
+        self.draw_game(game, &SprintTimer::from_ticks(game));
+        let (_, height) = Self::screen_size(game);
+        self.back.print(0, height, status, None);
+        self.back.print(0, height + 1, REPLAY_HELP, None);
+        self.present();
+    }
+
+    // like init, with room for the replay status
+    pub fn init_replay(&mut self, game: &Game) {
+        let (width, height) = Self::screen_size(game);
+        self.resize(width.max(REPLAY_HELP.len() as u16), height + 2);
+    }
+
+    // the space a game takes up: the well, the hud and the preview column
+    fn screen_size(game: &Game) -> (u16, u16) {
+        let board = game.board();
+        let width = Self::hud_x(game) + PREVIEW_COLUMN + PREVIEW_WIDTH;
+        let mut height = (board.height() as u16 + 1).max(2 + PREVIEW_SIZE as u16 * PREVIEW_ROWS).max(SEED_ROW + 1);
+        if let Some(target) = game.mode().target_lines() {
+            height = height.max(TIMER_ROW + 3 + (target / SPLIT_LINES) as u16);
+        }
+
+        (width, height)
+    }
+
+    // new buffers for a screen of this size, nothing on the terminal is known anymore
+    fn resize(&mut self, width: u16, height: u16) {
+        self.front = ScreenBuffer::new(width, height, UNKNOWN);
+        self.back = ScreenBuffer::new(width, height, BLANK);
+        execute!(self.stdout, Clear(ClearType::All)).unwrap();
+    }
+
+    // write out the cells that differ from what is on screen, then flush once
+    fn present(&mut self) {
+        let mut color = None;
+        let mut cursor = None;
+        queue!(self.stdout, ResetColor).unwrap();
+
+        for y in 0..self.back.height {
+            for x in 0..self.back.width {
+                let index = y as usize * self.back.width as usize + x as usize;
+                let cell = self.back.cells[index];
+                if cell == self.front.cells[index] {
+                    continue;
+                }
+
+                if cursor != Some((x, y)) {
+                    queue!(self.stdout, MoveTo(x, y)).unwrap();
+                }
+                if cell.color != color {
+                    match cell.color {
+                        Some(new_color) => queue!(self.stdout, SetForegroundColor(new_color)).unwrap(),
+                        None => queue!(self.stdout, ResetColor).unwrap(),
+                    }
+                    color = cell.color;
+                }
+                queue!(self.stdout, Print(cell.ch)).unwrap();
+                cursor = Some((x + 1, y));
+            }
+        }
+
+        queue!(self.stdout, ResetColor).unwrap();
+        self.stdout.flush().unwrap();
+        swap(&mut self.front, &mut self.back);
+    }
+}
+
+impl Renderer for TerminalRenderer {
+    // size the buffers for this board and forget what is on screen
+    fn init(&mut self, game: &Game) {
+        let (width, height) = Self::screen_size(game);
+        self.resize(width, height);
+    }
+
+    fn render(&mut self, game: &Game) {
+        self.draw_game(game, &SprintTimer::from_ticks(game));
+        self.present();
+    }
+}
\ No newline at end of file
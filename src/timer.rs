@@ -0,0 +1,91 @@
+use std::time::{Duration, Instant};
+
+use retris::{Game, GameEvent};
+
+// a live game's time and splits by the wall clock, to the millisecond and without the time
+// spent paused. logic ticks still decide when a sprint ends, the clock only times it
+#[derive(Debug, Clone)]
+pub struct SprintTimer {
+    running_since: Option<Instant>, // None while paused and once the game has ended
+    before: Duration, // time up to the last pause
+    splits: Vec<Duration>,
+}
+
+impl SprintTimer {
+    pub fn start() -> SprintTimer {
+        SprintTimer {
+            running_since: Some(Instant::now()),
+            before: Duration::ZERO,
+            splits: Vec::new(),
+        }
+    }
+
+    // a replay only knows the logic ticks it was played in, so it is timed by those
+    pub fn from_ticks(game: &Game) -> SprintTimer {
+        SprintTimer {
+            running_since: None,
+            before: game.elapsed(),
+            splits: game.splits().to_vec(),
+        }
+    }
+
+    pub fn pause(&mut self) {
+        if let Some(since) = self.running_since.take() {
+            self.before += since.elapsed();
+        }
+    }
+
+    pub fn resume(&mut self) {
+        self.running_since.get_or_insert_with(Instant::now);
+    }
+
+    pub fn time(&self) -> Duration {
+        self.before + self.running_since.map_or(Duration::ZERO, |since| since.elapsed())
+    }
+
+    pub fn splits(&self) -> &[Duration] {
+        &self.splits
+    }
+
+    // take the splits of a step, the clock stops once the game has ended
+    pub fn record(&mut self, events: &[GameEvent]) {
+        for event in events {
+            match event {
+                GameEvent::Split { .. } => self.splits.push(self.time()),
+                GameEvent::Finished { .. } | GameEvent::GameOver => self.pause(),
+                _ => (),
+            }
+        }
+    }
+}
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const NAP: Duration = Duration::from_millis(5);
+
+    #[test]
+    fn pauses_do_not_count() {
+        let mut timer = SprintTimer::start();
+        timer.pause();
+        let time = timer.time();
+        std::thread::sleep(NAP);
+        assert_eq!(timer.time(), time);
+
+        timer.resume();
+        std::thread::sleep(NAP);
+        assert!(timer.time() >= time + NAP);
+    }
+
+    #[test]
+    fn the_clock_stops_when_the_sprint_is_finished() {
+        let mut timer = SprintTimer::start();
+        timer.record(&[GameEvent::Split { lines: 10, time: Duration::ZERO }, GameEvent::Finished { time: Duration::ZERO }]);
+        let time = timer.time();
+        std::thread::sleep(NAP);
+
+        assert_eq!(timer.time(), time);
+        assert_eq!(timer.splits().len(), 1);
+        assert!(timer.splits()[0] <= time);
+    }
+}
\ No newline at end of file